@@ -0,0 +1,80 @@
+// Bullet vs monster hit detection, every bullet against every monster
+// compared to the grid broad phase of the collision system.
+//
+//     cargo bench --bench collision
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+use gun_score::collision::{Grid};
+use gun_score::config::{GameConfig};
+use gun_score::ecs::{Entities, Entity};
+use gun_score::ecs::components::{Team};
+use gun_score::ecs::systems::{self, Hit};
+use gun_score::geom::{Position};
+use gun_score::schemas::bullet::{Bullet};
+use gun_score::schemas::enemy::{EnemyType, Monster};
+use gun_score::util::{get_random_number, new_rng};
+
+// keep the crowd about as dense as in the game, whatever the count is.
+fn arena_side(count: usize) -> f64 {
+    (count as f64 * 2500.0).sqrt().max(500.0)
+}
+
+fn setup(count: usize) -> (f64, Entities, Vec<Entity>, Vec<Entity>) {
+    let config = GameConfig::default();
+    let mut rng = new_rng(7);
+    let side = arena_side(count);
+    let mut entities = Entities::new();
+
+    let monsters = (0..count)
+        .map(|_| {
+            let x = get_random_number::<f64>(&mut rng, 0.0, side);
+            let y = get_random_number::<f64>(&mut rng, 0.0, side);
+            Monster::spawn(&mut entities, Position::new(x, y), EnemyType::Kind, 1.0, &config.enemy, &mut rng)
+        })
+        .collect();
+    let bullets = (0..count)
+        .map(|_| {
+            let x = get_random_number::<f64>(&mut rng, 0.0, side);
+            let y = get_random_number::<f64>(&mut rng, 0.0, side);
+            Bullet::spawn(&mut entities, Position::new(x, y), 0.0, config.bullet.speed, config.bullet.lifetime, 10.0, Team::Player)
+        })
+        .collect();
+
+    (side, entities, bullets, monsters)
+}
+
+fn brute_force(entities: &Entities, bullets: &[Entity], monsters: &[Entity]) -> usize {
+    bullets.iter()
+        .filter(|bullet| monsters.iter().any(|monster| entities.swept_into(**bullet, *monster)))
+        .count()
+}
+
+fn grid(grid: &mut Grid, entities: &Entities, hits: &mut Vec<Hit>) -> usize {
+    systems::collision(entities, grid, hits);
+    hits.len()
+}
+
+fn bullets_vs_monsters(c: &mut Criterion) {
+    let mut group = c.benchmark_group("bullets_vs_monsters");
+    group.sample_size(10);
+
+    for count in [100, 1000, 5000] {
+        let (side, entities, bullets, monsters) = setup(count);
+        let mut broad_phase = Grid::new(side, side);
+        let mut hits = Vec::new();
+        assert_eq!(brute_force(&entities, &bullets, &monsters), grid(&mut broad_phase, &entities, &mut hits));
+
+        group.bench_with_input(BenchmarkId::new("brute_force", count), &count, |b, _| {
+            b.iter(|| brute_force(&entities, &bullets, &monsters))
+        });
+        group.bench_with_input(BenchmarkId::new("grid", count), &count, |b, _| {
+            b.iter(|| grid(&mut broad_phase, &entities, &mut hits))
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bullets_vs_monsters);
+criterion_main!(benches);
@@ -0,0 +1,200 @@
+// How enemies move: every <THINK_TIME> seconds each one looks for the player and picks
+// a new velocity from its `Behaviour`, running away instead once its health is low.
+
+use std::f64::consts::PI;
+
+use rand::Rng;
+
+use crate::config::{AiConfig, Behaviour};
+use crate::geom::{Position, Vec2};
+use crate::util::{get_random_number, GameRng};
+
+const THINK_TIME: f64 = 0.1; // 0.1 secs / 100 ms
+const WANDER_RADIUS: f64 = 10.0; // px moved in one think when wandering
+const PATROL_REACHED: f64 = 5.0; // px, close enough to a patrol point to turn around
+
+pub struct Brain {
+    config: AiConfig,
+    think_ttl: f64,
+    home: Position, // where the enemy spawned, one end of its patrol
+    patrol_to: Position,
+    orbit: f64, // 1 or -1, which way a strafing enemy circles
+    rng: GameRng,
+}
+
+impl Brain {
+    pub fn new(config: AiConfig, home: Position, mut rng: GameRng) -> Self {
+        let orbit = if rng.gen_bool(0.5) { 1.0 } else { -1.0 };
+        let patrol_to = Brain::patrol_point(&config, home, &mut rng);
+        Brain { config, think_ttl: 0.0, home, patrol_to, orbit, rng }
+    }
+
+    fn patrol_point(config: &AiConfig, home: Position, rng: &mut GameRng) -> Position {
+        let angle = get_random_number::<f64>(rng, 0.0, 2.0 * PI);
+        home + Vec2::from_angle(angle, config.patrol_radius)
+    }
+
+    // `health` is the share (0..1) left, `player` where the player is, if there is one.
+    pub fn think(&mut self, pos: Position, health: f64, player: Option<Position>, dt: f64, velocity: &mut Vec2) {
+        // Only change direction every <THINK_TIME> seconds
+        self.think_ttl -= dt;
+        if self.think_ttl > 0.0 {
+            return;
+        }
+        self.think_ttl = THINK_TIME;
+
+        let seen = player.filter(|player| pos.distance(player) <= self.config.sight);
+        let speed = self.config.speed;
+
+        if let Some(player) = seen {
+            if health < self.config.flee_health {
+                *velocity = (pos - player).normalize() * speed;
+                return;
+            }
+
+            if self.rng.gen_bool(self.config.aggression) {
+                match self.config.behaviour {
+                    Behaviour::Chase | Behaviour::Patrol => {
+                        *velocity = (player - pos).normalize() * speed;
+                        return;
+                    },
+                    Behaviour::Strafe => {
+                        *velocity = self.strafe(pos, player);
+                        return;
+                    },
+                    Behaviour::Wander => {},
+                }
+            }
+        } else if self.config.behaviour == Behaviour::Patrol {
+            *velocity = self.patrol(pos);
+            return;
+        }
+
+        *velocity = self.wander();
+    }
+
+    // circle around the player, while moving in or out towards the preferred distance.
+    fn strafe(&self, pos: Position, player: Position) -> Vec2 {
+        let away = pos - player;
+        let toward = -away.normalize();
+        let tangent = toward.rotate(PI / 2.0 * self.orbit);
+        let gap = away.length() - self.config.distance;
+        let radial = (gap / self.config.distance.max(1.0)).clamp(-1.0, 1.0);
+
+        (toward * radial + tangent).normalize() * self.config.speed
+    }
+
+    fn patrol(&mut self, pos: Position) -> Vec2 {
+        if pos.distance(&self.patrol_to) <= PATROL_REACHED {
+            // back home, then out to a new spot.
+            self.patrol_to = if self.patrol_to == self.home {
+                Brain::patrol_point(&self.config, self.home, &mut self.rng)
+            } else {
+                self.home
+            };
+        }
+
+        (self.patrol_to - pos).normalize() * self.config.speed
+    }
+
+    // Randomly wander up to <WANDER_RADIUS> in a random direction until the next think.
+    fn wander(&mut self) -> Vec2 {
+        Vec2::new(
+            (get_random_number::<f64>(&mut self.rng, 0.0, WANDER_RADIUS * 2.0) - WANDER_RADIUS) / THINK_TIME,
+            (get_random_number::<f64>(&mut self.rng, 0.0, WANDER_RADIUS * 2.0) - WANDER_RADIUS) / THINK_TIME,
+        )
+    }
+}
+
+// where to aim from `shooter` so a bullet of `speed` meets a target moving at `velocity`,
+// or straight at the target when the bullet can never catch up.
+pub fn lead(shooter: Position, target: Position, velocity: Vec2, speed: f64) -> Position {
+    // solve |target + velocity * t - shooter| = speed * t for the earliest t > 0.
+    let gap = target - shooter;
+    let a = velocity.dot(&velocity) - speed * speed;
+    let b = 2.0 * gap.dot(&velocity);
+    let c = gap.dot(&gap);
+
+    let time = if a.abs() < 1e-9 {
+        if b < 0.0 { Some(-c / b) } else { None }
+    } else {
+        let discriminant = b * b - 4.0 * a * c;
+        if discriminant < 0.0 {
+            None
+        } else {
+            let root = discriminant.sqrt();
+            [(-b - root) / (2.0 * a), (-b + root) / (2.0 * a)]
+                .into_iter()
+                .filter(|time| *time > 0.0)
+                .reduce(f64::min)
+        }
+    };
+
+    match time {
+        Some(time) => target + velocity * time,
+        None => target,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::{new_rng};
+
+    fn brain(behaviour: Behaviour) -> Brain {
+        let config = AiConfig { behaviour, aggression: 1.0, flee_health: 0.25, ..AiConfig::default() };
+        Brain::new(config, Position::new(0.0, 0.0), new_rng(1))
+    }
+
+    fn think(brain: &mut Brain, health: f64, player: Position) -> Vec2 {
+        let mut velocity = Vec2::zero();
+        brain.think(Position::new(0.0, 0.0), health, Some(player), THINK_TIME, &mut velocity);
+        velocity
+    }
+
+    #[test]
+    fn chases_the_player_in_sight() {
+        let mut chaser = brain(Behaviour::Chase);
+        assert_eq!(think(&mut chaser, 1.0, Position::new(100.0, 0.0)), Vec2::new(60.0, 0.0));
+    }
+
+    #[test]
+    fn flees_when_hurt() {
+        let mut chaser = brain(Behaviour::Chase);
+        assert_eq!(think(&mut chaser, 0.1, Position::new(100.0, 0.0)), Vec2::new(-60.0, 0.0));
+    }
+
+    #[test]
+    fn strafes_around_at_its_distance() {
+        let mut strafer = brain(Behaviour::Strafe);
+        // already at the preferred distance, so it only circles
+        let velocity = think(&mut strafer, 1.0, Position::new(150.0, 0.0));
+        assert!(velocity.x.abs() < 1e-9);
+        assert!((velocity.y.abs() - 60.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn leads_a_moving_target() {
+        let shooter = Position::new(0.0, 0.0);
+        let target = Position::new(100.0, 0.0);
+        // standing still, aim right at it
+        assert_eq!(lead(shooter, target, Vec2::zero(), 200.0), target);
+
+        // the bullet and the target meet at the aimed point at the same time
+        let velocity = Vec2::new(0.0, 50.0);
+        let aim = lead(shooter, target, velocity, 200.0);
+        let time = aim.distance(&shooter) / 200.0;
+        assert!((target + velocity * time).distance(&aim) < 1e-6);
+
+        // too fast to catch, aim where it is
+        assert_eq!(lead(shooter, target, Vec2::new(500.0, 0.0), 200.0), target);
+    }
+
+    #[test]
+    fn patrols_until_the_player_shows_up() {
+        let mut patrol = brain(Behaviour::Patrol);
+        let out = think(&mut patrol, 1.0, Position::new(1000.0, 0.0));
+        assert!((out.length() - 60.0).abs() < 1e-9);
+        assert_eq!(think(&mut patrol, 1.0, Position::new(0.0, 100.0)), Vec2::new(0.0, 60.0));
+    }
+}
@@ -2,160 +2,253 @@ use opengl_graphics::{GlGraphics, OpenGL};
 use glutin_window::GlutinWindow as Window;
 use piston::window::{WindowSettings, Size};
 use piston::input::*;
-use graphics::{clear};
+use graphics::{clear, rectangle};
 
 use crate::colors::{Colors};
-use crate::schemas::player::{Player};
-use crate::schemas::enemy::{Monster, EnemyType};
-use crate::schemas::bullet::{Bullet, Attacker};
-use crate::geom::{Direction, Position};
-use crate::schemas::{GameObject};
-use crate::textures::{load_cache, TextDraw};
-
-#[derive(PartialEq)]
-enum GameStatus {
-    Fight, // fighting mode
-    Win, // all monster have been killed
-    Lose, // could be dead
-}
-
-// fire cooldown
-const FIRE_COOLDOWN: f64 = 0.1; // 10 bulls/sec
-
+use crate::config::{GameConfig};
+use crate::level::{Level};
+use crate::controls::{Action, InputMapper, RawInput};
+use crate::geom::{Position};
+use crate::replay::{Replay, Playback};
+use crate::render::{render_entities};
+use crate::textures::{load_cache, get_icon, IconStruct, TextDraw};
+use crate::util::{random_seed};
+use crate::world::{GameWorld, GameStatus, GameInput, TICK_DT};
+
+// never try to catch up on more than this much time at once, e.g. after the window was dragged.
+const MAX_FRAME_TIME: f64 = 0.25;
+// size of the boss's health bar, px.
+const BOSS_BAR_WIDTH: f64 = 400.0;
+const BOSS_BAR_HEIGHT: f64 = 14.0;
+// size of the reload bar above the player, px.
+const RELOAD_BAR_WIDTH: f64 = 50.0;
+const RELOAD_BAR_HEIGHT: f64 = 6.0;
+
+// the piston front-end: owns the window and renderer, and drives a `GameWorld`.
 pub struct GunScoreApp<'a> {
     pub gl: GlGraphics,
     pub window: Window,
-    pub player: Player,
-    pub monsters: Vec<Monster>,
-    pub bullets: Vec<Bullet>,
+    pub world: GameWorld,
     pub text_draw: TextDraw<'a>,
+    pub icon: IconStruct,
+    pub controls: InputMapper,
+
+    // inputs received since the last update
+    pending: Vec<GameInput>,
+    recording: Option<Replay>,
+    playback: Option<Playback>,
+    accumulator: f64, // time not yet simulated
+}
 
-    // game state
-    status: GameStatus,
-    bullets_cooldown: f64,
+impl Default for GunScoreApp<'_> {
+    fn default() -> Self {
+        let config = GameConfig::default();
+        let level = Level::open_field(&config);
+        Self::new(config, level, random_seed())
+    }
 }
 
 impl GunScoreApp<'_> {
-    pub fn new() -> Self {
+    // the window is as big as the level's arena.
+    pub fn new(config: GameConfig, level: Level, seed: u64) -> Self {
         let opengl = OpenGL::V3_2;
-        let window_settings = WindowSettings::new("gun-score", [500, 500])
+        let window_settings = WindowSettings::new("gun-score", [level.width, level.height])
             .graphics_api(opengl)
+            .resizable(false) // the arena is fixed, so replays stay exact
             .exit_on_esc(true);
+        let window: Window = window_settings.build().unwrap();
         let glyph = load_cache(include_bytes!("../assets/SF_Atarian_System.ttf"));
         
         // glyph access on 'TextDraw.cache'
 
         GunScoreApp {
-            window: window_settings.build().unwrap(),
+            window,
             gl: GlGraphics::new(opengl),
-            player: Player::new(&"Tono".to_string(), &0.0, &0.0),
-            monsters: Vec::new(),
-            status: GameStatus::Fight,
+            controls: InputMapper::new(&config.controls),
+            world: GameWorld::new(config, level, seed),
             text_draw: TextDraw::new(glyph),
-            bullets: Vec::new(),
-            bullets_cooldown: 0.0,
+            icon: get_icon(&Position::new(0.0, 0.0)),
+            pending: Vec::new(),
+            recording: None,
+            playback: None,
+            accumulator: 0.0,
         }
     }
 
+    // record every button from now on, see `take_recording`.
+    pub fn start_recording(&mut self) {
+        self.recording = Some(Replay::new(self.world.seed, self.world.config.clone(), self.world.level.clone()));
+    }
+
+    pub fn take_recording(&mut self) -> Option<Replay> {
+        self.recording.take()
+    }
+
+    // watch a recorded run, live buttons are ignored while it plays.
+    pub fn play(&mut self, replay: Replay) {
+        self.world = replay.new_world();
+        self.controls = InputMapper::new(&replay.config.controls);
+        self.pending.clear();
+        self.playback = Some(Playback::new(replay));
+        self.accumulator = 0.0;
+    }
+
     pub fn render(&mut self, args: &RenderArgs) {
         let colors = Colors::init();
         let size = &self.get_size();
 
-        // player
-        let (x, y) = (self.player.pos.x, self.player.pos.y);
-        let player = &self.player;
+        let world = &self.world;
+        let icon = &self.icon;
+        let text_draw = &mut self.text_draw;
+        let playback = &self.playback;
+        let reload_key = self.controls.bindings.key_name(Action::Reload);
+        let restart_key = self.controls.bindings.key_name(Action::Restart);
+        let wave_count = world.level.waves(&world.config).len();
+        // blend between the last two ticks, including the time since the last update.
+        let alpha = ((self.accumulator + args.ext_dt) / TICK_DT).min(1.0);
 
-        // monster/enemy
-        let enemies = &self.monsters;
-        // bullets
-        let bullets = &self.bullets;
+        // player
+        let player = world.player();
+        let player_health = world.player_health();
+        let player_pos = world.entities.transforms.get(world.player)
+            .map_or(world.player_pos(), |transform| transform.interpolate(alpha));
 
         self.gl.draw(args.viewport(), |c, gl| {
             clear(colors.white, gl);
 
             // draw player's name
-            self.text_draw.draw(&String::from(format_args!("{} - ({:.2})", player.name.to_string(), player.health).to_string()), &colors.black, &[
-                                player.pos.x, (player.pos.y - 20.0),
+            text_draw.draw(&format!("{} - ({:.2})", player.name, player_health), &colors.black, &[
+                                player_pos.x, (player_pos.y - 20.0),
             ], &15, &c, gl);
             // draw player stats
-            self.text_draw.draw(&String::from(format_args!("Amunition: {}", player.amunition).to_string()), &colors.black, &[
+            let weapon = player.weapon();
+            let reserve = weapon.reserve.map_or(String::from("endless"), |reserve| reserve.to_string());
+            text_draw.draw(&format!("{}: {}/{} | {}", weapon.config.name, weapon.rounds, weapon.config.magazine, reserve), &colors.black, &[
                                 20.0, 40.0
             ], &25, &c, gl);
-            self.text_draw.draw(&String::from(format_args!("Score: {}", player.score.floor()).to_string()), &colors.black, &[
+
+            // the reload's progress above the player.
+            if let Some(progress) = weapon.reload_progress() {
+                let (x, y) = (player_pos.x - RELOAD_BAR_WIDTH / 2.0, player_pos.y - 45.0);
+                rectangle(colors.black, [x - 1.0, y - 1.0, RELOAD_BAR_WIDTH + 2.0, RELOAD_BAR_HEIGHT + 2.0], c.transform, gl);
+                rectangle(colors.white, [x, y, RELOAD_BAR_WIDTH, RELOAD_BAR_HEIGHT], c.transform, gl);
+                rectangle(colors.blue, [x, y, RELOAD_BAR_WIDTH * progress, RELOAD_BAR_HEIGHT], c.transform, gl);
+            }
+            text_draw.draw(&format!("Score: {}", player.score.floor()), &colors.black, &[
                                 20.0, 70.0,
             ], &25, &c, gl);
-            
-            self.text_draw.draw(&String::from(format_args!("Life: {}", player.life).to_string()), &colors.black, &[
-                                f64::from(size.width) / 2.0,
-								(f64::from(size.height) / 2.0) + 20.0,
+            text_draw.draw(&format!("Wave: {}/{}", world.waves.number().min(wave_count), wave_count), &colors.black, &[
+                                20.0, 100.0,
+            ], &25, &c, gl);
+
+            // the running buffs with their time left.
+            for (index, (kind, buff)) in player.buffs.iter().enumerate() {
+                text_draw.draw(&format!("{}: {:.1}s", kind.title(), buff.left), &kind.color(), &[
+                                    20.0, 130.0 + 20.0 * index as f64,
+                ], &15, &c, gl);
+            }
+
+            text_draw.draw(&format!("Life: {}", player.life), &colors.black, &[
+                                size.width / 2.0,
+								(size.height / 2.0) + 20.0,
             ], &15, &c, gl);
 
-            // draw "Need reload ..." text when the player amunition is 0
-            if self.player.amunition <= 0 {
-                self.text_draw.draw(&String::from("Need reload, press (R)"), &colors.red, &[
-                                    (f64::from(size.width) / 1.5),
-                                    (f64::from(size.height) / 1.5)
+            // every weapon slot, with the one in hand marked.
+            for (slot, weapon) in player.weapons.iter().enumerate() {
+                let color = if slot == player.weapon { colors.red } else { colors.black };
+                let mode = if weapon.config.automatic { " (auto)" } else { "" };
+                text_draw.draw(&format!("{} {}{}", slot + 1, weapon.config.name, mode), &color, &[
+                                    20.0, size.height - 50.0 - 20.0 * (player.weapons.len() - 1 - slot) as f64,
+                ], &15, &c, gl);
+            }
+
+            // draw "Need reload ..." text when the weapon in hand is empty, or "Out of ammo" when there is nothing left
+            if weapon.is_dry() {
+                text_draw.draw(&String::from("Out of ammo, switch weapons"), &colors.red, &[
+                                    (size.width / 1.5),
+                                    (size.height / 1.5)
+                ], &20, &c, gl);
+            } else if weapon.is_empty() && weapon.reloading.is_none() {
+                text_draw.draw(&format!("Need reload, press ({})", reload_key), &colors.red, &[
+                                    (size.width / 1.5),
+                                    (size.height / 1.5)
                 ], &20, &c, gl);
             }
 
             // check the game status.
-            match self.status {
+            match world.status {
                 GameStatus::Lose => {
-                    self.text_draw.draw_center(&String::from("YOU LOSE!"), &colors.red, &40, &[
-                                               f64::from(size.width),
-                                               f64::from(size.height),
+                    text_draw.draw_center(&String::from("YOU LOSE!"), &colors.red, &40, &[
+                                               size.width,
+                                               size.height,
                     ], &c,gl);
 
-                    self.text_draw.draw(&String::from("Press (1 / One) to restart"), &colors.blue, &[
-                                        (f64::from(size.width) / 1.5),
-                                        (f64::from(size.height) / 1.5) + 20.0,
+                    text_draw.draw(&format!("Press ({}) to restart", restart_key), &colors.blue, &[
+                                        (size.width / 1.5),
+                                        (size.height / 1.5) + 20.0,
                     ], &20, &c, gl);
                 },
                 GameStatus::Win => {
-                    self.text_draw.draw_center(&String::from("You are the Winner!"), &colors.green, &40, &[
-                                                             f64::from(size.width),
-                                                             f64::from(size.height),
+                    text_draw.draw_center(&String::from("You are the Winner!"), &colors.green, &40, &[
+                                                             size.width,
+                                                             size.height,
                     ], &c, gl);
 
-                    self.text_draw.draw(&String::from("Press (1) to restart"), &colors.blue, &[
-                                        (f64::from(size.width) / 1.5),
-                                        (f64::from(size.height) / 1.5) + 20.0,
+                    text_draw.draw(&format!("Press ({}) to restart", restart_key), &colors.blue, &[
+                                        (size.width / 1.5),
+                                        (size.height / 1.5) + 20.0,
                     ], &20, &c, gl);
                 },
+                GameStatus::Fight if world.waves.is_resting() && world.waves.is_boss(world.level.waves(&world.config)) => {
+                    text_draw.draw_center(&String::from("Boss incoming!"), &colors.red, &40, &[
+                                                             size.width,
+                                                             size.height,
+                    ], &c, gl);
+                },
+                GameStatus::Fight if world.waves.is_resting() => {
+                    text_draw.draw_center(&format!("Wave {}", world.waves.number()), &colors.blue, &40, &[
+                                                             size.width,
+                                                             size.height,
+                    ], &c, gl);
+                },
                 GameStatus::Fight => {
-                    self.text_draw.draw_center(&String::from("Keep fight!"), &colors.black, &32, &[
-                                                             f64::from(size.width),
-                                                             f64::from(size.height),
+                    text_draw.draw_center(&String::from("Keep fight!"), &colors.black, &32, &[
+                                                             size.width,
+                                                             size.height,
                     ], &c, gl);
                 }
             }
 
-            // render player
-            player.render(&c, gl);
-
-            // render monster/enemy
-            for enemy in enemies.iter() {
-                enemy.render(&c, gl);
+            // the boss's health bar along the top, with a notch where each of its next phases starts.
+            if let Some((boss, health)) = world.boss() {
+                let (width, height) = (BOSS_BAR_WIDTH, BOSS_BAR_HEIGHT);
+                let x = (size.width - width) / 2.0;
+                text_draw.draw(&boss.name, &colors.black, &[x, 30.0], &20, &c, gl);
+                rectangle(colors.black, [x - 2.0, 38.0, width + 4.0, height + 4.0], c.transform, gl);
+                rectangle(colors.white, [x, 40.0, width, height], c.transform, gl);
+                rectangle(colors.red, [x, 40.0, width * health, height], c.transform, gl);
+                for phase in boss.phases.iter().skip(1) {
+                    rectangle(colors.black, [x + width * phase.from - 1.0, 40.0, 2.0, height], c.transform, gl);
+                }
             }
 
-            // render bullets
-            for bullet in bullets.iter() {
-                bullet.render(&c, gl);
+            if world.paused {
+                text_draw.draw_center(&String::from("Paused"), &colors.blue, &32, &[
+                                                         size.width,
+                                                         size.height + 80.0,
+                ], &c, gl);
             }
-        });
-
-        drop(size);
-        drop(colors);
-        drop(x);
-        drop(y);
-    }
 
-    fn reset(&mut self) {
-        self.player = Player::new(&"You".to_string(), &0.0, &0.0);
+            if let Some(playback) = playback {
+                let text = if playback.is_finished() { "Replay finished" } else { "Replay" };
+                text_draw.draw(&String::from(text), &colors.blue, &[
+                                    20.0, size.height - 20.0,
+                ], &20, &c, gl);
+            }
 
-        self.status = GameStatus::Fight;
-        self.monsters.clear();
-        self.bullets.clear();
+            // render player, monsters/enemies and bullets
+            render_entities(&world.entities, icon, alpha, &c, gl);
+        });
     }
 
     fn get_size(&self) -> Size {
@@ -164,159 +257,57 @@ impl GunScoreApp<'_> {
         (size.width, size.height).into()
     }
 
+    // run as many fixed ticks as the elapsed time covers, the rest is left for the next frame.
     pub fn update(&mut self, args: &UpdateArgs) {
-        let size = &self.get_size();
-        // player update
-        self.player.update(args.dt, *size);
-        
-        // enemies/monsters update
-        if self.monsters.is_empty() {
-            for _ in 1..10 {
-                self.monsters.push(Monster::new_rand(f64::from(size.width), f64::from(size.height)));
-            }
+        self.accumulator = (self.accumulator + args.dt).min(MAX_FRAME_TIME);
+        while self.accumulator >= TICK_DT {
+            self.accumulator -= TICK_DT;
+            self.tick();
         }
+    }
 
-        if self.bullets_cooldown > 0.0 {
-            self.bullets_cooldown -= args.dt;
+    fn tick(&mut self) {
+        if let Some(playback) = &mut self.playback {
+            playback.step(&mut self.world);
+            return;
         }
 
-        if self.player.shooting {
-            self.player.shooting = false;
-            if self.player.amunition >= 1 {
-                self.bullets.push(
-                    Bullet::new(self.player.pos.x, self.player.pos.y, self.player.direction, Attacker::Player),
-                );
-                self.player.amunition -= 1;
-            }
+        if let Some(recording) = &mut self.recording {
+            recording.record_tick();
         }
 
-		// only works in fight mode.
-        if self.status == GameStatus::Fight {	
-			if self.player.health < f64::from(1.0) {
-				self.player.life -= 1;
-				self.player.pos = Position::new(0.0, 0.0);
-			}
-			
-			if self.player.life < u8::from(1) {
-				self.status = GameStatus::Lose;
-			}
-			
-			for monster in &mut self.monsters {
-				monster.update(args.dt, *size);
-				if monster.tabrakan(&self.player) {
-					if self.player.life < u8::from(1) {
-						self.status = GameStatus::Lose;
-					} else {
-						self.player.life -= 1;
-						self.player.pos = Position::new(0.0, 0.0); // reset the position when the player died.
-						self.player.health = 1000;
-					}
-				}
-			}
-			
-			let mut enem_bulls: Vec<Bullet> = Vec::new();
-			
-			for bullet in &mut self.bullets {
-				bullet.update(args.dt, *size);
-				for monster in &mut self.monsters {
-					if bullet.tabrakan(monster) && bullet.attacker == Attacker::Player {
-						// if the monster type is a fighter, it will shoot a bullet to player.
-						if monster.enemy_type == EnemyType::Fighter {
-							enem_bulls.push(Bullet::new(monster.pos.x, monster.pos.y, bullet.source_direction, Attacker::Monster));
-						}
-
-						bullet.ttl = 0.0;
-						let health = monster.health;
-
-						monster.health -= f64::from(bullet.damage_count);
-						// if the monster health <= 0.0, and the bullet is coming from Player.
-						// Add (health - (health / Bullet#damage_count)) as score to player.
-						if monster.health <= 0.0 && bullet.attacker == Attacker::Player {
-							self.player.score += health - (health - f64::from(bullet.damage_count));
-						}
-
-						drop(health);
-					} else if bullet.tabrakan(&self.player) && bullet.attacker == Attacker::Monster {
-						bullet.ttl = 0.0;
-						self.player.health -= f64::from(bullet.damage_count);
-					}
-				}
-			}
-
-			self.bullets.append(&mut enem_bulls);
-			drop(enem_bulls);
-
-			self.bullets.retain(|bullet| bullet.ttl > 0.0);
-			self.monsters.retain(|monster| monster.health > 0.0);
-			
-			if self.monsters.is_empty() {
-				self.status = GameStatus::Win;
-			}
-        } else {
-			// just update the position.
-			for bullet in &mut self.bullets {
-				bullet.update(args.dt, *size);
-			}
-			
-			for monster in &mut self.monsters {
-				monster.update(args.dt, *size);
-			}
-		}
-
-        drop(size);
+        self.world.step(TICK_DT, &self.pending);
+        self.pending.clear();
     }
 
     pub fn input(&mut self, button: &Button, is_press: bool) {
-        match button {
-            Button::Keyboard(key) => {
-                match key {
-                    Key::W => {
-                        if is_press {
-                            self.player.start_move(Direction::NORTH)
-                        } else {
-                            self.player.stop_move(Direction::NORTH)
-                        }
-                    },
-                    Key::S => {
-                        if is_press {
-                            self.player.start_move(Direction::SOUTH)
-                        } else {
-                            self.player.stop_move(Direction::SOUTH)
-                        }
-                    },
-                    Key::A => {
-                        if is_press {
-                            self.player.start_move(Direction::WEST)
-                        } else {
-                            self.player.stop_move(Direction::WEST)
-                        }
-                    },
-                    Key::D => {
-                        if is_press {
-                            self.player.start_move(Direction::EAST)
-                        } else {
-                            self.player.stop_move(Direction::EAST)
-                        }
-                    },
-                    Key::Space => {
-                        if is_press && self.bullets_cooldown <= 0.0 {
-                            self.bullets_cooldown = FIRE_COOLDOWN;
-                            self.player.shooting = true;
-                        }
-                    },
-                    Key::R => {
-                        if is_press && self.player.amunition <= 0 {
-                            self.player.shooting = false;
-                            self.player.amunition = 100;
-                        }
-                    },
-                    Key::D1 => {
-                        self.reset();
-                    }
-                    _ => (),
-                }
-            }
-            _ => (),
+        self.raw_input(RawInput::Button { button: *button, pressed: is_press });
+    }
+
+    pub fn cursor(&mut self, pos: [f64; 2]) {
+        self.raw_input(RawInput::Cursor(pos));
+    }
+
+    pub fn scroll(&mut self, amount: [f64; 2]) {
+        self.raw_input(RawInput::Scroll(amount));
+    }
+
+    // controller sticks and triggers.
+    pub fn axis(&mut self, args: &ControllerAxisArgs) {
+        self.raw_input(RawInput::Axis(*args));
+    }
+
+    fn raw_input(&mut self, input: RawInput) {
+        if self.playback.is_some() {
+            return;
+        }
+
+        if let Some(recording) = &mut self.recording {
+            recording.record_input(input);
+        }
+
+        if let Some(input) = self.controls.map(&input) {
+            self.pending.push(input);
         }
     }
 }
@@ -0,0 +1,48 @@
+use std::env;
+use std::process;
+
+pub struct Options {
+    pub config: Option<String>, // game config (TOML)
+    pub level: Option<String>, // level file (TOML)
+    pub seed: Option<u64>,
+    pub record: Option<String>, // write the run to this replay file
+    pub replay: Option<String>, // watch this replay file
+    pub verify: Option<String>, // play this replay file without a window
+}
+
+fn usage() -> ! {
+    eprintln!("usage: gun-score [--config <file>] [--level <file>] [--seed <number>] [--record <file>] [--replay <file>] [--verify <file>]");
+    process::exit(2);
+}
+
+pub fn parse_args() -> Options {
+    let mut options = Options { config: None, level: None, seed: None, record: None, replay: None, verify: None };
+    let mut args = env::args().skip(1);
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--seed" => {
+                let value = args.next().unwrap_or_else(|| usage());
+                match value.parse::<u64>() {
+                    Ok(seed) => options.seed = Some(seed),
+                    Err(_) => {
+                        eprintln!("invalid seed: {}", value);
+                        usage();
+                    }
+                }
+            },
+            "--config" => options.config = Some(args.next().unwrap_or_else(|| usage())),
+            "--level" => options.level = Some(args.next().unwrap_or_else(|| usage())),
+            "--record" => options.record = Some(args.next().unwrap_or_else(|| usage())),
+            "--replay" => options.replay = Some(args.next().unwrap_or_else(|| usage())),
+            "--verify" => options.verify = Some(args.next().unwrap_or_else(|| usage())),
+            "-h" | "--help" => usage(),
+            _ => {
+                eprintln!("unknown argument: {}", arg);
+                usage();
+            }
+        }
+    }
+
+    options
+}
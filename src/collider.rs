@@ -0,0 +1,227 @@
+use crate::geom::{Vec2};
+
+// the shape an object collides with, centered on its position.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum Collider {
+    Circle { radius: f64 },
+    // axis-aligned box, `half` is half of its width and height.
+    Aabb { half: Vec2 },
+    // a box rotated by `angle` (radians) around its center.
+    Obb { half: Vec2, angle: f64 },
+}
+
+impl Collider {
+    // do the two shapes touch or overlap? Being fully inside the other counts too.
+    pub fn overlaps(&self, pos: &Vec2, other: &Collider, other_pos: &Vec2) -> bool {
+        match (*self, *other) {
+            (Collider::Circle { radius }, Collider::Circle { radius: other_radius }) => {
+                let reach = radius + other_radius;
+                (*other_pos - *pos).length_squared() <= reach * reach
+            },
+            (Collider::Circle { radius }, _) => circle_box(pos, radius, other, other_pos),
+            (_, Collider::Circle { radius }) => circle_box(other_pos, radius, self, pos),
+            (Collider::Aabb { half }, Collider::Aabb { half: other_half }) => {
+                let gap = *other_pos - *pos;
+                gap.x.abs() <= half.x + other_half.x && gap.y.abs() <= half.y + other_half.y
+            },
+            _ => boxes(self.as_box(), pos, other.as_box(), other_pos),
+        }
+    }
+
+    // like `overlaps`, but for a shape moving in a straight line from `from` to `to`,
+    // so a fast object can't jump over something thin within one tick.
+    // The moving shape is treated as its bounding circle.
+    pub fn sweep(&self, from: &Vec2, to: &Vec2, other: &Collider, other_pos: &Vec2) -> bool {
+        let radius = self.bounding_radius();
+        match *other {
+            Collider::Circle { radius: other_radius } => {
+                let reach = radius + other_radius;
+                segment_point_distance_squared(from, to, other_pos) <= reach * reach
+            },
+            _ => {
+                // the box grown by `radius` has rounded corners: two crossed boxes plus four circles.
+                let (half, angle) = other.as_box();
+                let a = (*from - *other_pos).rotate(-angle);
+                let b = (*to - *other_pos).rotate(-angle);
+                segment_hits_box(&a, &b, &Vec2::new(half.x + radius, half.y))
+                    || segment_hits_box(&a, &b, &Vec2::new(half.x, half.y + radius))
+                    || [(1.0, 1.0), (1.0, -1.0), (-1.0, 1.0), (-1.0, -1.0)].iter().any(|(sx, sy)| {
+                        let corner = Vec2::new(half.x * sx, half.y * sy);
+                        segment_point_distance_squared(&a, &b, &corner) <= radius * radius
+                    })
+            },
+        }
+    }
+
+    // the radius of the smallest circle around the shape, for quick rejection.
+    pub fn bounding_radius(&self) -> f64 {
+        match self {
+            Collider::Circle { radius } => *radius,
+            Collider::Aabb { half } | Collider::Obb { half, .. } => half.length(),
+        }
+    }
+
+    // the shape once its owner is turned by `angle`, only a rotated box cares.
+    pub fn turned(&self, angle: f64) -> Collider {
+        match *self {
+            Collider::Obb { half, angle: own } => Collider::Obb { half, angle: own + angle },
+            shape => shape,
+        }
+    }
+
+    // (half extents, angle) of a box shape.
+    fn as_box(&self) -> (Vec2, f64) {
+        match self {
+            Collider::Aabb { half } => (*half, 0.0),
+            Collider::Obb { half, angle } => (*half, *angle),
+            Collider::Circle { radius } => (Vec2::new(*radius, *radius), 0.0),
+        }
+    }
+}
+
+// move the circle into the box's own (unrotated) frame, then find the closest point of the box.
+fn circle_box(center: &Vec2, radius: f64, shape: &Collider, box_pos: &Vec2) -> bool {
+    let (half, angle) = shape.as_box();
+    let local = (*center - *box_pos).rotate(-angle);
+    let closest = Vec2::new(local.x.clamp(-half.x, half.x), local.y.clamp(-half.y, half.y));
+    (local - closest).length_squared() <= radius * radius
+}
+
+fn segment_point_distance_squared(a: &Vec2, b: &Vec2, point: &Vec2) -> f64 {
+    let segment = *b - *a;
+    let length = segment.length_squared();
+    let t = if length == 0.0 { 0.0 } else { ((*point - *a).dot(&segment) / length).clamp(0.0, 1.0) };
+    (*point - a.lerp(b, t)).length_squared()
+}
+
+// slab test of the segment `a`..`b` against a box centered on the origin.
+fn segment_hits_box(a: &Vec2, b: &Vec2, half: &Vec2) -> bool {
+    let delta = *b - *a;
+    let (mut enter, mut exit) = (0.0_f64, 1.0_f64);
+    for (start, step, reach) in [(a.x, delta.x, half.x), (a.y, delta.y, half.y)] {
+        if step == 0.0 {
+            if start.abs() > reach {
+                return false;
+            }
+            continue;
+        }
+
+        let (t1, t2) = ((-reach - start) / step, (reach - start) / step);
+        enter = enter.max(t1.min(t2));
+        exit = exit.min(t1.max(t2));
+        if enter > exit {
+            return false;
+        }
+    }
+
+    true
+}
+
+// separating axis test, two boxes only miss each other if one of their 4 edge normals separates them.
+fn boxes(a: (Vec2, f64), a_pos: &Vec2, b: (Vec2, f64), b_pos: &Vec2) -> bool {
+    let gap = *b_pos - *a_pos;
+    let axes = |angle: f64| [Vec2::from_angle(angle, 1.0), Vec2::from_angle(angle + std::f64::consts::FRAC_PI_2, 1.0)];
+    let (a_axes, b_axes) = (axes(a.1), axes(b.1));
+
+    // how far a box reaches along `axis` from its center.
+    let reach = |half: Vec2, own: &[Vec2; 2], axis: &Vec2| {
+        half.x * own[0].dot(axis).abs() + half.y * own[1].dot(axis).abs()
+    };
+
+    a_axes.iter().chain(b_axes.iter()).all(|axis| {
+        gap.dot(axis).abs() <= reach(a.0, &a_axes, axis) + reach(b.0, &b_axes, axis)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::FRAC_PI_4;
+
+    fn at(x: f64, y: f64) -> Vec2 {
+        Vec2::new(x, y)
+    }
+
+    fn hits(a: Collider, a_pos: Vec2, b: Collider, b_pos: Vec2) -> bool {
+        let hit = a.overlaps(&a_pos, &b, &b_pos);
+        assert_eq!(hit, b.overlaps(&b_pos, &a, &a_pos), "overlap must not depend on the order");
+        hit
+    }
+
+    const SMALL: Collider = Collider::Circle { radius: 2.0 };
+    const BIG: Collider = Collider::Circle { radius: 10.0 };
+    const SQUARE: Collider = Collider::Aabb { half: Vec2 { x: 10.0, y: 10.0 } };
+    const DIAMOND: Collider = Collider::Obb { half: Vec2 { x: 10.0, y: 10.0 }, angle: FRAC_PI_4 };
+
+    #[test]
+    fn circle_circle() {
+        assert!(hits(SMALL, at(0.0, 0.0), BIG, at(11.0, 0.0)));
+        assert!(!hits(SMALL, at(0.0, 0.0), BIG, at(12.5, 0.0)));
+        // fully inside the big one is still a hit
+        assert!(hits(SMALL, at(1.0, 1.0), BIG, at(0.0, 0.0)));
+    }
+
+    #[test]
+    fn circle_aabb() {
+        assert!(hits(SMALL, at(11.5, 0.0), SQUARE, at(0.0, 0.0)));
+        assert!(!hits(SMALL, at(12.5, 0.0), SQUARE, at(0.0, 0.0)));
+        // near the corner the circle is round, not square
+        assert!(!hits(SMALL, at(11.8, 11.8), SQUARE, at(0.0, 0.0)));
+        assert!(hits(SMALL, at(0.0, 0.0), SQUARE, at(0.0, 0.0)));
+    }
+
+    #[test]
+    fn circle_obb() {
+        // the diamond reaches ~14.1 along the x axis, but only 10 along its diagonal
+        assert!(hits(SMALL, at(15.0, 0.0), DIAMOND, at(0.0, 0.0)));
+        assert!(!hits(SMALL, at(9.5, 9.5), DIAMOND, at(0.0, 0.0)));
+        assert!(hits(SMALL, at(0.0, 0.0), DIAMOND, at(0.0, 0.0)));
+    }
+
+    #[test]
+    fn aabb_aabb() {
+        assert!(hits(SQUARE, at(0.0, 0.0), SQUARE, at(20.0, 19.0)));
+        assert!(!hits(SQUARE, at(0.0, 0.0), SQUARE, at(20.5, 0.0)));
+        let thin = Collider::Aabb { half: at(1.0, 1.0) };
+        assert!(hits(thin, at(3.0, 3.0), SQUARE, at(0.0, 0.0)));
+    }
+
+    #[test]
+    fn aabb_obb() {
+        assert!(hits(SQUARE, at(0.0, 0.0), DIAMOND, at(24.0, 0.0)));
+        // the corners of the square and the diamond point past each other
+        assert!(!hits(SQUARE, at(0.0, 0.0), DIAMOND, at(24.5, 24.5)));
+        assert!(!hits(SQUARE, at(0.0, 0.0), DIAMOND, at(25.0, 0.0)));
+    }
+
+    #[test]
+    fn sweep_through_circle() {
+        // one tick jumps from one side of the circle to the other
+        assert!(SMALL.sweep(&at(-50.0, 0.0), &at(50.0, 0.0), &BIG, &at(0.0, 0.0)));
+        assert!(!SMALL.overlaps(&at(50.0, 0.0), &BIG, &at(0.0, 0.0)));
+        assert!(!SMALL.sweep(&at(-50.0, 13.0), &at(50.0, 13.0), &BIG, &at(0.0, 0.0)));
+        // not moving at all is a plain overlap test
+        assert!(SMALL.sweep(&at(11.0, 0.0), &at(11.0, 0.0), &BIG, &at(0.0, 0.0)));
+    }
+
+    #[test]
+    fn sweep_through_boxes() {
+        assert!(SMALL.sweep(&at(-50.0, 11.5), &at(50.0, 11.5), &SQUARE, &at(0.0, 0.0)));
+        assert!(!SMALL.sweep(&at(-50.0, 12.5), &at(50.0, 12.5), &SQUARE, &at(0.0, 0.0)));
+        // passing by a corner, rounded by the bullet's radius
+        assert!(!SMALL.sweep(&at(0.0, 24.0), &at(24.0, 0.0), &SQUARE, &at(0.0, 0.0)));
+        assert!(SMALL.sweep(&at(0.0, 22.0), &at(22.0, 0.0), &SQUARE, &at(0.0, 0.0)));
+        // the diamond's tip sticks out along x
+        assert!(SMALL.sweep(&at(15.0, -50.0), &at(15.0, 50.0), &DIAMOND, &at(0.0, 0.0)));
+        assert!(!SMALL.sweep(&at(17.0, -50.0), &at(17.0, 50.0), &DIAMOND, &at(0.0, 0.0)));
+    }
+
+    #[test]
+    fn obb_obb() {
+        assert!(hits(DIAMOND, at(0.0, 0.0), DIAMOND, at(28.0, 0.0)));
+        assert!(!hits(DIAMOND, at(0.0, 0.0), DIAMOND, at(28.5, 0.0)));
+        let long = Collider::Obb { half: at(30.0, 1.0), angle: FRAC_PI_4 };
+        assert!(hits(long, at(0.0, 0.0), DIAMOND, at(20.0, 20.0)));
+        assert!(!hits(long, at(0.0, 0.0), DIAMOND, at(20.0, -20.0)));
+    }
+}
@@ -0,0 +1,99 @@
+use crate::geom::{Vec2};
+
+// side of one grid cell, a bit bigger than the largest enemy.
+pub const CELL_SIZE: f64 = 64.0;
+
+// Broad phase: a uniform grid over the arena, so a bullet only has to be tested
+// against the objects sharing its cells instead of every object in the world.
+pub struct Grid {
+    cols: usize,
+    rows: usize,
+    cells: Vec<Vec<usize>>,
+}
+
+impl Grid {
+    pub fn new(width: f64, height: f64) -> Self {
+        let cols = ((width / CELL_SIZE).ceil() as usize).max(1);
+        let rows = ((height / CELL_SIZE).ceil() as usize).max(1);
+        Grid { cols, rows, cells: vec![Vec::new(); cols * rows] }
+    }
+
+    pub fn clear(&mut self) {
+        for cell in &mut self.cells {
+            cell.clear();
+        }
+    }
+
+    // remember `index`, e.g. an entity's, in every cell the circle covers.
+    pub fn insert(&mut self, index: usize, pos: &Vec2, radius: f64) {
+        let (min, max) = self.cell_range(pos, radius);
+        for row in min.1..=max.1 {
+            for col in min.0..=max.0 {
+                self.cells[row * self.cols + col].push(index);
+            }
+        }
+    }
+
+    // indices of everything that might touch a circle at `pos`, in ascending order and without repeats.
+    pub fn query(&self, pos: &Vec2, radius: f64, found: &mut Vec<usize>) {
+        found.clear();
+        let (min, max) = self.cell_range(pos, radius);
+        for row in min.1..=max.1 {
+            for col in min.0..=max.0 {
+                found.extend_from_slice(&self.cells[row * self.cols + col]);
+            }
+        }
+
+        found.sort_unstable();
+        found.dedup();
+    }
+
+    // the first and last (col, row) covered by the circle, anything outside the arena lands on the border cells.
+    fn cell_range(&self, pos: &Vec2, radius: f64) -> ((usize, usize), (usize, usize)) {
+        let col = |x: f64| ((x / CELL_SIZE).floor().max(0.0) as usize).min(self.cols - 1);
+        let row = |y: f64| ((y / CELL_SIZE).floor().max(0.0) as usize).min(self.rows - 1);
+        (
+            (col(pos.x - radius), row(pos.y - radius)),
+            (col(pos.x + radius), row(pos.y + radius)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query(grid: &Grid, x: f64, y: f64, radius: f64) -> Vec<usize> {
+        let mut found = Vec::new();
+        grid.query(&Vec2::new(x, y), radius, &mut found);
+        found
+    }
+
+    #[test]
+    fn finds_an_entity_spanning_several_cells_once() {
+        let mut grid = Grid::new(500.0, 500.0);
+        // a big one on the corner of four cells, and a small one far away
+        grid.insert(3, &Vec2::new(CELL_SIZE, CELL_SIZE), 40.0);
+        grid.insert(7, &Vec2::new(400.0, 400.0), 5.0);
+
+        for (x, y) in [(10.0, 10.0), (CELL_SIZE + 10.0, 10.0), (10.0, CELL_SIZE + 10.0), (CELL_SIZE + 10.0, CELL_SIZE + 10.0)] {
+            assert_eq!(query(&grid, x, y, 1.0), vec![3]);
+        }
+        assert_eq!(query(&grid, CELL_SIZE, CELL_SIZE, 400.0), vec![3, 7]);
+        assert!(query(&grid, 250.0, 250.0, 1.0).is_empty());
+    }
+
+    #[test]
+    fn outside_the_arena_lands_on_the_border_cells() {
+        let mut grid = Grid::new(500.0, 500.0);
+        grid.insert(1, &Vec2::new(-50.0, -50.0), 1.0);
+        grid.insert(2, &Vec2::new(900.0, 300.0), 1.0);
+
+        assert_eq!(query(&grid, 5.0, 5.0, 1.0), vec![1]);
+        assert_eq!(query(&grid, 495.0, 300.0, 1.0), vec![2]);
+        assert_eq!(query(&grid, 1000.0, 300.0, 1.0), vec![2]);
+
+        grid.clear();
+        assert!(query(&grid, 5.0, 5.0, 1.0).is_empty());
+    }
+}
@@ -0,0 +1,1083 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use piston::input::{Key, MouseButton};
+use serde::{Deserialize, Serialize};
+
+use crate::controls::{Action};
+use crate::level::{MAX_SIZE};
+use crate::schemas::enemy::{EnemyType};
+use crate::schemas::pickup::{PickupKind};
+
+// every tunable number of the game, loaded from a TOML file.
+// Missing keys fall back to the defaults below, unknown keys are an error.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct GameConfig {
+    pub window: WindowConfig,
+    pub player: PlayerConfig,
+    pub bullet: BulletConfig,
+    pub weapons: Vec<WeaponConfig>, // the player's guns, in the order of the number keys
+    pub enemy: EnemyConfig,
+    pub waves: Vec<WaveConfig>, // played in order, then comes the boss
+    pub boss: BossConfig,
+    pub pickups: PickupConfig,
+    pub controls: ControlsConfig,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct WindowConfig {
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct PlayerConfig {
+    pub speed: f64, // max speed, px/sec
+    pub acceleration: f64, // px/sec^2
+    pub friction: f64, // px/sec^2, how fast the player stops once nothing is held
+    pub health: f64,
+    pub life: u8,
+    pub auto_reload: bool, // start reloading once the magazine runs empty
+    pub invulnerability: f64, // secs the player can't be hurt after respawning
+    pub safe_distance: f64, // px from the nearest monster the player respawns at, when there is room
+}
+
+// every bullet, the enemies' ones fly at `speed` and hurt as much as their shooter does.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct BulletConfig {
+    pub speed: f64, // px/sec
+    pub lifetime: f64, // secs
+}
+
+// one of the player's guns.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct WeaponConfig {
+    pub name: String,
+    pub magazine: u32, // rounds
+    #[serde(default)]
+    pub reserve: Option<u32>, // rounds carried besides the magazine, endless when not given
+    pub reload_time: f64, // secs
+    pub fire_cooldown: f64, // secs between two shots
+    #[serde(default)]
+    pub automatic: bool, // keeps firing while the trigger is held, otherwise once per press
+    pub spread: f64, // radians every bullet randomly goes off by, at most
+    #[serde(default = "one")]
+    pub pellets: u32, // bullets in one shot
+    pub speed: f64, // px/sec
+    pub damage: [f32; 2], // [min, max), of every bullet
+    #[serde(default)]
+    pub pierce: u32, // targets a bullet flies on through before it is used up
+    #[serde(default)]
+    pub splash: Option<ExplosionConfig>, // the bullet blows up on hit instead
+}
+
+// the stats table, one `[enemy.<type>]` section for every `EnemyType`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct EnemyConfig {
+    pub kind: EnemyStats,
+    pub fighter: EnemyStats,
+    pub charger: EnemyStats,
+    pub bruiser: EnemyStats,
+    pub splitter: EnemyStats,
+    pub exploder: EnemyStats,
+    pub shielded: EnemyStats,
+}
+
+// Everything one type of enemy is made of. A section in the config replaces the whole default
+// of its type, so `health`, `damage`, `size` and `color` must be given, the rest is optional.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct EnemyStats {
+    pub health: [f64; 2], // [min, max)
+    pub damage: [f32; 2], // [min, max), dealt by its bullets or its explosion
+    pub size: f64, // px, side of its square body
+    pub color: String, // hex, e.g. "F33333"
+    #[serde(default)]
+    pub ai: AiConfig,
+    #[serde(default)]
+    pub gun: Option<EnemyGunConfig>, // shoots at the player
+    #[serde(default)]
+    pub split: Option<SplitConfig>, // breaks up into smaller enemies when killed
+    #[serde(default)]
+    pub explosion: Option<ExplosionConfig>, // blows up when killed or touching the player
+    #[serde(default)]
+    pub shield: f64, // radians either side of its front that bullets can't get through, 0 for none
+    #[serde(default = "shield_turn")]
+    pub shield_turn: f64, // radians/sec its front turns towards the player, so it can be flanked
+    #[serde(default = "contact_damage")]
+    pub contact: f64, // health/sec it takes off the player while touching, times the wave's difficulty
+}
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct SplitConfig {
+    pub count: usize,
+    pub into: EnemyType,
+}
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ExplosionConfig {
+    pub radius: f64, // px, everything of the other team this close gets the enemy's damage
+}
+
+// how an enemy shoots at the player.
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct EnemyGunConfig {
+    pub cooldown: f64, // secs between two shots
+    pub range: f64, // px, only fires at a player this close and in sight
+    pub lead: bool, // aim where the player will be when the bullet arrives
+    pub spread: f64, // radians, shots go off by up to this much either way
+}
+
+// what an enemy does once it spots the player.
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Behaviour {
+    Wander, // ignore the player and jitter around
+    Chase, // run straight at the player
+    Strafe, // keep `distance` away and circle around the player
+    Patrol, // walk back and forth near its spawn, chase the player when seen
+}
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct AiConfig {
+    pub behaviour: Behaviour,
+    pub speed: f64, // px/sec
+    pub sight: f64, // px, the player is noticed within this range
+    pub aggression: f64, // chance (0..1) to act on the behaviour instead of wandering, checked every 100 ms
+    pub distance: f64, // px, how far a strafing enemy stays away
+    pub flee_health: f64, // share (0..1) of health under which it runs away, 0 never flees
+    pub patrol_radius: f64, // px, how far from its spawn a patrol goes
+}
+
+// the big one that comes after the last wave, the run is won once it is dead.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct BossConfig {
+    pub enabled: bool, // false wins the run right after the last wave
+    pub name: String, // shown above its health bar
+    pub health: f64, // multiplied by the last wave's difficulty, like its damage
+    pub damage: [f32; 2], // [min, max), dealt by its bullets
+    pub contact: f64, // health/sec it takes off the player while touching, times the difficulty
+    pub size: f64, // px, side of its square body
+    pub color: String, // hex, e.g. "2B2B2B"
+    pub delay: f64, // secs of rest before it comes
+    pub ai: AiConfig,
+    pub phases: Vec<BossPhase>, // the first starts at full health, each of the next takes over lower
+}
+
+// what the boss does while its health is at or below `from`, until the next phase takes over.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct BossPhase {
+    pub from: f64, // share (0..1] of its health
+    pub attacks: Vec<BossAttack>, // all of them run side by side, each on its own cooldown
+}
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq)]
+#[serde(tag = "pattern", rename_all = "snake_case", deny_unknown_fields)]
+pub enum BossAttack {
+    // a fan of `bullets` spanning `arc` radians, centered on the player.
+    Spread { cooldown: f64, bullets: usize, arc: f64 },
+    // `bullets` evenly around the boss, the whole ring turned by `turn` radians every volley.
+    Spiral { cooldown: f64, bullets: usize, turn: f64 },
+    // `count` enemies of type `enemy` around the boss.
+    Summon { cooldown: f64, count: usize, enemy: EnemyType },
+}
+
+// what killed monsters leave behind for the player to walk over.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct PickupConfig {
+    pub chance: f64, // share (0..1) of the killed monsters that drop something
+    pub lifetime: f64, // secs a pickup lies around before it is gone
+    pub mix: BTreeMap<PickupKind, f64>, // how likely each kind is, e.g. { health = 3.0, life = 0.5 }
+    pub health: f64, // healed by a health pickup, never above the player's full health
+    pub ammo: u32, // magazines every weapon's reserve gets from an ammo pickup
+    pub duration: f64, // secs the rapid fire, shield, damage and speed buffs last
+    pub rapid_fire: f64, // multiplies the fire rate
+    pub damage: f64, // multiplies the damage of the player's bullets
+    pub speed: f64, // multiplies the player's max speed
+}
+
+// where the monsters of a wave show up.
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SpawnPattern {
+    Random, // anywhere in the arena
+    Edges, // along the walls
+    Ring, // evenly around the middle of the arena
+    Zones, // inside the level's spawn zones
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct WaveConfig {
+    pub count: usize, // monsters in the wave
+    pub mix: BTreeMap<EnemyType, f64>, // how likely each type is, e.g. { kind = 3.0, fighter = 1.0 }
+    pub pattern: SpawnPattern,
+    pub delay: f64, // secs of rest before the wave comes
+    pub difficulty: f64, // multiplies the monsters' health and damage
+}
+
+// every action can be bound to any number of keys, named like piston's `Key` (e.g. "W", "Up", "Space", "D1").
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct ControlsConfig {
+    pub move_up: Vec<Key>,
+    pub move_down: Vec<Key>,
+    pub move_left: Vec<Key>,
+    pub move_right: Vec<Key>,
+    pub fire: Vec<Key>,
+    pub reload: Vec<Key>,
+    pub restart: Vec<Key>,
+    pub pause: Vec<Key>,
+    pub next_weapon: Vec<Key>,
+    pub prev_weapon: Vec<Key>,
+    pub weapons: Vec<Vec<Key>>, // the keys of each weapon slot, in order
+    pub mouse: MouseConfig,
+    pub gamepad: GamepadConfig,
+}
+
+// mouse buttons are named like piston's `MouseButton` (e.g. "Left", "Right", "Middle").
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct MouseConfig {
+    pub aim: bool, // point the gun at the cursor
+    pub wheel: bool, // switch weapons with the mouse wheel
+    pub fire: Vec<MouseButton>,
+    pub reload: Vec<MouseButton>,
+}
+
+impl MouseConfig {
+    pub fn actions(&self) -> [(Action, &Vec<MouseButton>); 2] {
+        [
+            (Action::Fire, &self.fire),
+            (Action::Reload, &self.reload),
+        ]
+    }
+}
+
+// controller buttons and axes are numbers, as reported by the window backend.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct GamepadConfig {
+    pub deadzone: f64, // part (0..1) of the stick's travel that is ignored
+    pub move_x_axis: u8,
+    pub move_y_axis: u8,
+    pub fire_axes: Vec<u8>, // triggers that fire when pulled past half way
+    pub fire: Vec<u8>,
+    pub reload: Vec<u8>,
+    pub restart: Vec<u8>,
+    pub pause: Vec<u8>,
+    pub next_weapon: Vec<u8>,
+    pub prev_weapon: Vec<u8>,
+}
+
+impl GamepadConfig {
+    pub fn actions(&self) -> [(Action, &Vec<u8>); 6] {
+        [
+            (Action::Fire, &self.fire),
+            (Action::Reload, &self.reload),
+            (Action::Restart, &self.restart),
+            (Action::Pause, &self.pause),
+            (Action::NextWeapon, &self.next_weapon),
+            (Action::PrevWeapon, &self.prev_weapon),
+        ]
+    }
+
+    fn key_of(action: Action) -> &'static str {
+        match action {
+            Action::Reload => "controls.gamepad.reload",
+            Action::Restart => "controls.gamepad.restart",
+            Action::Pause => "controls.gamepad.pause",
+            Action::NextWeapon => "controls.gamepad.next_weapon",
+            Action::PrevWeapon => "controls.gamepad.prev_weapon",
+            _ => "controls.gamepad.fire",
+        }
+    }
+}
+
+impl ControlsConfig {
+    pub fn actions(&self) -> Vec<(Action, &Vec<Key>)> {
+        let mut actions = vec![
+            (Action::MoveUp, &self.move_up),
+            (Action::MoveDown, &self.move_down),
+            (Action::MoveLeft, &self.move_left),
+            (Action::MoveRight, &self.move_right),
+            (Action::Fire, &self.fire),
+            (Action::Reload, &self.reload),
+            (Action::Restart, &self.restart),
+            (Action::Pause, &self.pause),
+            (Action::NextWeapon, &self.next_weapon),
+            (Action::PrevWeapon, &self.prev_weapon),
+        ];
+        actions.extend(self.weapons.iter().enumerate().map(|(slot, keys)| (Action::Weapon(slot), keys)));
+        actions
+    }
+
+    fn key_of(action: Action) -> String {
+        match action {
+            Action::MoveUp => String::from("controls.move_up"),
+            Action::MoveDown => String::from("controls.move_down"),
+            Action::MoveLeft => String::from("controls.move_left"),
+            Action::MoveRight => String::from("controls.move_right"),
+            Action::Fire => String::from("controls.fire"),
+            Action::Reload => String::from("controls.reload"),
+            Action::Restart => String::from("controls.restart"),
+            Action::Pause => String::from("controls.pause"),
+            Action::NextWeapon => String::from("controls.next_weapon"),
+            Action::PrevWeapon => String::from("controls.prev_weapon"),
+            Action::Weapon(slot) => format!("controls.weapons[{}]", slot),
+        }
+    }
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        GameConfig {
+            window: WindowConfig::default(),
+            player: PlayerConfig::default(),
+            bullet: BulletConfig::default(),
+            weapons: WeaponConfig::defaults(),
+            enemy: EnemyConfig::default(),
+            waves: WaveConfig::defaults(),
+            boss: BossConfig::default(),
+            pickups: PickupConfig::default(),
+            controls: ControlsConfig::default(),
+        }
+    }
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        WindowConfig { width: 500, height: 500 }
+    }
+}
+
+impl Default for PlayerConfig {
+    fn default() -> Self {
+        PlayerConfig {
+            speed: 600.0,
+            acceleration: 6000.0,
+            friction: 3000.0,
+            health: 1000.0,
+            life: 5,
+            auto_reload: true,
+            invulnerability: 2.5,
+            safe_distance: 200.0,
+        }
+    }
+}
+
+impl Default for BulletConfig {
+    fn default() -> Self {
+        BulletConfig {
+            speed: 240.0,
+            lifetime: 2.0,
+        }
+    }
+}
+
+fn one() -> u32 {
+    1
+}
+
+fn shield_turn() -> f64 {
+    1.5
+}
+
+fn contact_damage() -> f64 {
+    200.0
+}
+
+impl WeaponConfig {
+    // endless reserve and a reload of a second, unless changed afterwards.
+    fn new(name: &str, magazine: u32, fire_cooldown: f64, spread: f64, speed: f64, damage: [f32; 2]) -> Self {
+        WeaponConfig {
+            name: name.to_string(),
+            magazine,
+            reserve: None,
+            reload_time: 1.0,
+            fire_cooldown,
+            automatic: false,
+            spread,
+            pellets: 1,
+            speed,
+            damage,
+            pierce: 0,
+            splash: None,
+        }
+    }
+
+    pub fn defaults() -> Vec<WeaponConfig> {
+        vec![
+            WeaponConfig::new("Pistol", 15, 0.25, 0.02, 300.0, [10.0, 20.0]),
+            WeaponConfig {
+                reserve: Some(30),
+                reload_time: 2.0,
+                pellets: 7,
+                ..WeaponConfig::new("Shotgun", 6, 0.8, 0.3, 260.0, [5.0, 10.0])
+            },
+            WeaponConfig {
+                reserve: Some(160),
+                reload_time: 1.5,
+                automatic: true,
+                ..WeaponConfig::new("SMG", 40, 0.06, 0.12, 280.0, [4.0, 9.0])
+            },
+            WeaponConfig {
+                reserve: Some(20),
+                reload_time: 2.5,
+                pierce: 3,
+                ..WeaponConfig::new("Sniper", 5, 1.2, 0.0, 700.0, [60.0, 90.0])
+            },
+            WeaponConfig {
+                reserve: Some(9),
+                reload_time: 3.0,
+                splash: Some(ExplosionConfig { radius: 60.0 }),
+                ..WeaponConfig::new("Rocket", 3, 1.5, 0.03, 200.0, [40.0, 60.0])
+            },
+        ]
+    }
+
+    pub fn validate(&self, section: &str) -> Result<(), ConfigError> {
+        if self.name.is_empty() {
+            return Err(invalid(format!("{}.name", section), "must not be empty"));
+        }
+        positive(&format!("{}.magazine", section), f64::from(self.magazine))?;
+        positive(&format!("{}.reload_time", section), self.reload_time)?;
+        positive(&format!("{}.fire_cooldown", section), self.fire_cooldown)?;
+        not_negative(&format!("{}.spread", section), self.spread)?;
+        positive(&format!("{}.pellets", section), f64::from(self.pellets))?;
+        positive(&format!("{}.speed", section), self.speed)?;
+        range(&format!("{}.damage", section), self.damage)?;
+        if let Some(splash) = &self.splash {
+            positive(&format!("{}.splash.radius", section), splash.radius)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for ControlsConfig {
+    fn default() -> Self {
+        ControlsConfig {
+            move_up: vec![Key::W, Key::Up],
+            move_down: vec![Key::S, Key::Down],
+            move_left: vec![Key::A, Key::Left],
+            move_right: vec![Key::D, Key::Right],
+            fire: vec![Key::Space],
+            reload: vec![Key::R],
+            restart: vec![Key::Return],
+            pause: vec![Key::P],
+            next_weapon: vec![Key::E],
+            prev_weapon: vec![Key::Q],
+            weapons: vec![vec![Key::D1], vec![Key::D2], vec![Key::D3], vec![Key::D4], vec![Key::D5]],
+            mouse: MouseConfig::default(),
+            gamepad: GamepadConfig::default(),
+        }
+    }
+}
+
+impl Default for MouseConfig {
+    fn default() -> Self {
+        MouseConfig {
+            aim: true,
+            wheel: true,
+            fire: vec![MouseButton::Left],
+            reload: vec![MouseButton::Right],
+        }
+    }
+}
+
+impl Default for GamepadConfig {
+    fn default() -> Self {
+        GamepadConfig {
+            deadzone: 0.2,
+            move_x_axis: 0, // left stick
+            move_y_axis: 1,
+            fire_axes: vec![5], // right trigger
+            fire: vec![0], // A
+            reload: vec![2], // X
+            restart: vec![4], // Back
+            pause: vec![6], // Start
+            next_weapon: vec![10], // right shoulder
+            prev_weapon: vec![9], // left shoulder
+        }
+    }
+}
+
+impl Default for EnemyConfig {
+    fn default() -> Self {
+        EnemyConfig {
+            kind: EnemyStats::new([10.0, 100.0], [0.0, 0.0], 30.0, "549EA0", AiConfig::default()),
+            fighter: EnemyStats {
+                gun: Some(EnemyGunConfig::default()),
+                ..EnemyStats::new([20.0, 1000.0], [10.0, 60.0], 30.0, "F33333", AiConfig {
+                    behaviour: Behaviour::Strafe,
+                    speed: 80.0,
+                    sight: 300.0,
+                    aggression: 0.8,
+                    flee_health: 0.25,
+                    ..AiConfig::default()
+                })
+            },
+            charger: EnemyStats::new([10.0, 40.0], [0.0, 0.0], 20.0, "F39C33", AiConfig::chase(180.0, 400.0, 1.0)),
+            bruiser: EnemyStats {
+                contact: 400.0,
+                ..EnemyStats::new([400.0, 900.0], [0.0, 0.0], 50.0, "5B3A7A", AiConfig::chase(35.0, 350.0, 0.9))
+            },
+            splitter: EnemyStats {
+                split: Some(SplitConfig { count: 3, into: EnemyType::Charger }),
+                ..EnemyStats::new([60.0, 120.0], [0.0, 0.0], 36.0, "3AA05B", AiConfig::chase(60.0, 300.0, 0.7))
+            },
+            exploder: EnemyStats {
+                explosion: Some(ExplosionConfig { radius: 70.0 }),
+                ..EnemyStats::new([20.0, 40.0], [100.0, 200.0], 26.0, "E8C21B", AiConfig::chase(110.0, 350.0, 1.0))
+            },
+            shielded: EnemyStats {
+                shield: 2.2,
+                ..EnemyStats::new([80.0, 160.0], [0.0, 0.0], 34.0, "8A8A8A", AiConfig::chase(50.0, 350.0, 0.9))
+            },
+        }
+    }
+}
+
+impl EnemyConfig {
+    pub fn stats(&self, enemy_type: EnemyType) -> &EnemyStats {
+        match enemy_type {
+            EnemyType::Kind => &self.kind,
+            EnemyType::Fighter => &self.fighter,
+            EnemyType::Charger => &self.charger,
+            EnemyType::Bruiser => &self.bruiser,
+            EnemyType::Splitter => &self.splitter,
+            EnemyType::Exploder => &self.exploder,
+            EnemyType::Shielded => &self.shielded,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        for enemy_type in EnemyType::ALL {
+            let section = format!("enemy.{}", enemy_type.name());
+            let stats = self.stats(enemy_type);
+            range(&format!("{}.health", section), stats.health)?;
+            range(&format!("{}.damage", section), stats.damage)?;
+            positive(&format!("{}.size", section), stats.size)?;
+            if !is_hex_color(&stats.color) {
+                return Err(invalid(format!("{}.color", section), "must be a hex color like \"F33333\""));
+            }
+            not_negative(&format!("{}.shield", section), stats.shield)?;
+            not_negative(&format!("{}.shield_turn", section), stats.shield_turn)?;
+            not_negative(&format!("{}.contact", section), stats.contact)?;
+            stats.ai.validate(&format!("{}.ai", section))?;
+
+            if let Some(gun) = &stats.gun {
+                positive(&format!("{}.gun.cooldown", section), gun.cooldown)?;
+                not_negative(&format!("{}.gun.range", section), gun.range)?;
+                not_negative(&format!("{}.gun.spread", section), gun.spread)?;
+            }
+            if let Some(split) = &stats.split {
+                positive(&format!("{}.split.count", section), split.count as f64)?;
+                // a splitter splitting into splitters would never end.
+                if self.stats(split.into).split.is_some() {
+                    return Err(invalid(format!("{}.split.into", section), "must be a type that doesn't split itself"));
+                }
+            }
+            if let Some(explosion) = &stats.explosion {
+                positive(&format!("{}.explosion.radius", section), explosion.radius)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl EnemyStats {
+    fn new(health: [f64; 2], damage: [f32; 2], size: f64, color: &str, ai: AiConfig) -> Self {
+        EnemyStats {
+            health,
+            damage,
+            size,
+            color: color.to_string(),
+            ai,
+            gun: None,
+            split: None,
+            explosion: None,
+            shield: 0.0,
+            shield_turn: shield_turn(),
+            contact: contact_damage(),
+        }
+    }
+}
+
+fn is_hex_color(color: &str) -> bool {
+    matches!(color.len(), 6 | 8) && color.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+impl Default for EnemyGunConfig {
+    fn default() -> Self {
+        EnemyGunConfig {
+            cooldown: 1.2,
+            range: 350.0,
+            lead: true,
+            spread: 0.1,
+        }
+    }
+}
+
+impl Default for AiConfig {
+    fn default() -> Self {
+        AiConfig {
+            behaviour: Behaviour::Patrol,
+            speed: 60.0,
+            sight: 200.0,
+            aggression: 0.6,
+            distance: 150.0,
+            flee_health: 0.0,
+            patrol_radius: 80.0,
+        }
+    }
+}
+
+impl AiConfig {
+    fn chase(speed: f64, sight: f64, aggression: f64) -> Self {
+        AiConfig { behaviour: Behaviour::Chase, speed, sight, aggression, ..AiConfig::default() }
+    }
+
+    // `section` is where it sits in the config, e.g. "enemy.kind.ai".
+    fn validate(&self, section: &str) -> Result<(), ConfigError> {
+        positive(&format!("{}.speed", section), self.speed)?;
+        not_negative(&format!("{}.sight", section), self.sight)?;
+        not_negative(&format!("{}.distance", section), self.distance)?;
+        not_negative(&format!("{}.patrol_radius", section), self.patrol_radius)?;
+        share(&format!("{}.aggression", section), self.aggression)?;
+        share(&format!("{}.flee_health", section), self.flee_health)
+    }
+}
+
+impl Default for BossConfig {
+    fn default() -> Self {
+        BossConfig {
+            enabled: true,
+            name: String::from("The Warden"),
+            health: 1500.0,
+            damage: [15.0, 30.0],
+            contact: 400.0,
+            size: 80.0,
+            color: String::from("2B2B2B"),
+            delay: 4.0,
+            ai: AiConfig {
+                behaviour: Behaviour::Strafe,
+                speed: 40.0,
+                sight: 1000.0,
+                aggression: 0.7,
+                distance: 220.0,
+                ..AiConfig::default()
+            },
+            phases: vec![
+                BossPhase {
+                    from: 1.0,
+                    attacks: vec![BossAttack::Spread { cooldown: 1.4, bullets: 5, arc: 0.6 }],
+                },
+                BossPhase {
+                    from: 0.66,
+                    attacks: vec![
+                        BossAttack::Spiral { cooldown: 0.15, bullets: 3, turn: 0.25 },
+                        BossAttack::Spread { cooldown: 1.8, bullets: 7, arc: 0.9 },
+                    ],
+                },
+                BossPhase {
+                    from: 0.33,
+                    attacks: vec![
+                        BossAttack::Summon { cooldown: 6.0, count: 3, enemy: EnemyType::Charger },
+                        BossAttack::Spiral { cooldown: 0.12, bullets: 4, turn: -0.3 },
+                    ],
+                },
+            ],
+        }
+    }
+}
+
+impl BossAttack {
+    pub fn cooldown(&self) -> f64 {
+        match *self {
+            BossAttack::Spread { cooldown, .. } | BossAttack::Spiral { cooldown, .. } | BossAttack::Summon { cooldown, .. } => cooldown,
+        }
+    }
+}
+
+impl BossConfig {
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if !self.enabled {
+            return Ok(());
+        }
+
+        positive("boss.health", self.health)?;
+        range("boss.damage", self.damage)?;
+        not_negative("boss.contact", self.contact)?;
+        positive("boss.size", self.size)?;
+        if !is_hex_color(&self.color) {
+            return Err(invalid("boss.color", "must be a hex color like \"2B2B2B\""));
+        }
+        not_negative("boss.delay", self.delay)?;
+        self.ai.validate("boss.ai")?;
+
+        if self.phases.first().is_none_or(|phase| phase.from != 1.0) {
+            return Err(invalid("boss.phases", "must start with a phase `from = 1.0`"));
+        }
+        for (index, phase) in self.phases.iter().enumerate() {
+            let section = format!("boss.phases[{}]", index);
+            if !(phase.from > 0.0 && phase.from <= 1.0) {
+                return Err(invalid(format!("{}.from", section), "must be between 0 (excluded) and 1"));
+            }
+            if index > 0 && phase.from >= self.phases[index - 1].from {
+                return Err(invalid(format!("{}.from", section), "must be lower than the phase before"));
+            }
+            for (index, attack) in phase.attacks.iter().enumerate() {
+                let section = format!("{}.attacks[{}]", section, index);
+                let (cooldown, count) = match *attack {
+                    BossAttack::Spread { cooldown, bullets, arc } => {
+                        not_negative(&format!("{}.arc", section), arc)?;
+                        (cooldown, ("bullets", bullets))
+                    },
+                    BossAttack::Spiral { cooldown, bullets, turn } => {
+                        if !turn.is_finite() {
+                            return Err(invalid(format!("{}.turn", section), "must be a number"));
+                        }
+                        (cooldown, ("bullets", bullets))
+                    },
+                    BossAttack::Summon { cooldown, count, .. } => (cooldown, ("count", count)),
+                };
+                positive(&format!("{}.cooldown", section), cooldown)?;
+                positive(&format!("{}.{}", section, count.0), count.1 as f64)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for PickupConfig {
+    fn default() -> Self {
+        use PickupKind::*;
+        PickupConfig {
+            chance: 0.15,
+            lifetime: 10.0,
+            mix: BTreeMap::from([
+                (Health, 3.0), (Ammo, 3.0), (Life, 0.5), (RapidFire, 1.0), (Shield, 1.0), (Damage, 1.0), (Speed, 1.0),
+            ]),
+            health: 250.0,
+            ammo: 1,
+            duration: 8.0,
+            rapid_fire: 2.0,
+            damage: 2.0,
+            speed: 1.5,
+        }
+    }
+}
+
+impl PickupConfig {
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        share("pickups.chance", self.chance)?;
+        positive("pickups.lifetime", self.lifetime)?;
+        for (kind, weight) in &self.mix {
+            not_negative(&format!("pickups.mix.{}", kind.name()), *weight)?;
+        }
+        positive("pickups.mix", self.mix.values().sum())?;
+        not_negative("pickups.health", self.health)?;
+        positive("pickups.duration", self.duration)?;
+        positive("pickups.rapid_fire", self.rapid_fire)?;
+        positive("pickups.damage", self.damage)?;
+        positive("pickups.speed", self.speed)
+    }
+
+    // how much the buff of `kind` multiplies its stat by, 1 for the kinds that aren't buffs.
+    pub fn boost(&self, kind: PickupKind) -> f64 {
+        match kind {
+            PickupKind::RapidFire => self.rapid_fire,
+            PickupKind::Damage => self.damage,
+            PickupKind::Speed => self.speed,
+            _ => 1.0,
+        }
+    }
+}
+
+impl Default for WaveConfig {
+    fn default() -> Self {
+        WaveConfig {
+            count: 9,
+            mix: BTreeMap::from([(EnemyType::Kind, 1.0), (EnemyType::Fighter, 1.0)]),
+            pattern: SpawnPattern::Random,
+            delay: 3.0,
+            difficulty: 1.0,
+        }
+    }
+}
+
+impl WaveConfig {
+    fn new(count: usize, mix: &[(EnemyType, f64)], pattern: SpawnPattern, delay: f64, difficulty: f64) -> Self {
+        WaveConfig { count, mix: mix.iter().copied().collect(), pattern, delay, difficulty }
+    }
+
+    pub fn validate(&self, section: &str) -> Result<(), ConfigError> {
+        positive(&format!("{}.count", section), self.count as f64)?;
+        positive(&format!("{}.difficulty", section), self.difficulty)?;
+        not_negative(&format!("{}.delay", section), self.delay)?;
+        for (enemy_type, weight) in &self.mix {
+            not_negative(&format!("{}.mix.{}", section, enemy_type.name()), *weight)?;
+        }
+        positive(&format!("{}.mix", section), self.mix.values().sum())
+    }
+
+    // the waves played when the config doesn't list any, each one a bit harder.
+    pub fn defaults() -> Vec<WaveConfig> {
+        use EnemyType::*;
+        vec![
+            WaveConfig::new(5, &[(Kind, 4.0), (Fighter, 1.0)], SpawnPattern::Random, 1.0, 1.0),
+            WaveConfig::new(8, &[(Kind, 3.0), (Fighter, 2.0), (Charger, 2.0)], SpawnPattern::Edges, 3.0, 1.2),
+            WaveConfig::new(10, &[(Fighter, 2.0), (Charger, 2.0), (Bruiser, 1.0), (Splitter, 1.0)], SpawnPattern::Ring, 3.0, 1.4),
+            WaveConfig::new(14, &[(Fighter, 2.0), (Charger, 2.0), (Exploder, 2.0), (Shielded, 1.0)], SpawnPattern::Edges, 3.0, 1.7),
+            WaveConfig::new(18, &[(Fighter, 3.0), (Bruiser, 1.0), (Splitter, 2.0), (Exploder, 2.0), (Shielded, 2.0)], SpawnPattern::Ring, 3.0, 2.0),
+        ]
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(io::Error),
+    Parse(toml::de::Error),
+    Invalid { key: String, reason: &'static str },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(err) => write!(f, "unable to read the config file: {}", err),
+            ConfigError::Parse(err) => write!(f, "the config file is broken: {}", err),
+            ConfigError::Invalid { key, reason } => write!(f, "`{}` {}", key, reason),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl From<io::Error> for ConfigError {
+    fn from(err: io::Error) -> Self {
+        ConfigError::Io(err)
+    }
+}
+
+impl From<toml::de::Error> for ConfigError {
+    fn from(err: toml::de::Error) -> Self {
+        ConfigError::Parse(err)
+    }
+}
+
+fn invalid(key: impl Into<String>, reason: &'static str) -> ConfigError {
+    ConfigError::Invalid { key: key.into(), reason }
+}
+
+fn positive(key: &str, value: f64) -> Result<(), ConfigError> {
+    if value > 0.0 && value.is_finite() {
+        Ok(())
+    } else {
+        Err(invalid(key, "must be greater than 0"))
+    }
+}
+
+fn not_negative(key: &str, value: f64) -> Result<(), ConfigError> {
+    if value >= 0.0 && value.is_finite() {
+        Ok(())
+    } else {
+        Err(invalid(key, "must not be below 0"))
+    }
+}
+
+fn share(key: &str, value: f64) -> Result<(), ConfigError> {
+    if (0.0..=1.0).contains(&value) {
+        Ok(())
+    } else {
+        Err(invalid(key, "must be between 0 and 1"))
+    }
+}
+
+// a fixed value can be written as [value, value].
+fn range<T: PartialOrd + Copy + Into<f64>>(key: &str, value: [T; 2]) -> Result<(), ConfigError> {
+    if !value.iter().all(|bound| (*bound).into().is_finite()) {
+        Err(invalid(key, "must be written as [min, max] with two numbers"))
+    } else if value[0].into() < 0.0 {
+        Err(invalid(key, "must not start below 0"))
+    } else if value[0] > value[1] {
+        Err(invalid(key, "must be written as [min, max] with min <= max"))
+    } else {
+        Ok(())
+    }
+}
+
+impl GameConfig {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<GameConfig, ConfigError> {
+        GameConfig::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn parse(text: &str) -> Result<GameConfig, ConfigError> {
+        let config: GameConfig = toml::from_str(text)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        for (key, value) in [("window.width", self.window.width), ("window.height", self.window.height)] {
+            positive(key, f64::from(value))?;
+            // the open field is as big as the window.
+            if f64::from(value) > MAX_SIZE {
+                return Err(invalid(key, "must be at most 4096"));
+            }
+        }
+
+        positive("player.speed", self.player.speed)?;
+        positive("player.acceleration", self.player.acceleration)?;
+        positive("player.friction", self.player.friction)?;
+        positive("player.health", self.player.health)?;
+        positive("player.life", f64::from(self.player.life))?;
+        not_negative("player.invulnerability", self.player.invulnerability)?;
+        not_negative("player.safe_distance", self.player.safe_distance)?;
+
+        positive("bullet.speed", self.bullet.speed)?;
+        positive("bullet.lifetime", self.bullet.lifetime)?;
+
+        if self.weapons.is_empty() {
+            return Err(invalid("weapons", "must list at least one weapon"));
+        }
+        for (index, weapon) in self.weapons.iter().enumerate() {
+            weapon.validate(&format!("weapons[{}]", index))?;
+        }
+
+        self.enemy.validate()?;
+
+        if self.waves.is_empty() {
+            return Err(invalid("waves", "must list at least one wave"));
+        }
+        for (index, wave) in self.waves.iter().enumerate() {
+            wave.validate(&format!("waves[{}]", index))?;
+        }
+        self.boss.validate()?;
+        self.pickups.validate()?;
+
+        // one key can't do two things at once.
+        let mut seen = Vec::new();
+        for (action, keys) in self.controls.actions() {
+            for key in keys {
+                if seen.contains(key) {
+                    return Err(ConfigError::Invalid {
+                        key: ControlsConfig::key_of(action),
+                        reason: "uses a key that is already bound to another action",
+                    });
+                }
+                seen.push(*key);
+            }
+        }
+
+        let mouse = &self.controls.mouse;
+        if mouse.fire.iter().any(|button| mouse.reload.contains(button)) {
+            return Err(ConfigError::Invalid {
+                key: String::from("controls.mouse.reload"),
+                reason: "uses a button that is already bound to another action",
+            });
+        }
+
+        let gamepad = &self.controls.gamepad;
+        if !(0.0..1.0).contains(&gamepad.deadzone) {
+            return Err(invalid("controls.gamepad.deadzone", "must be between 0 and 1"));
+        }
+
+        if gamepad.move_x_axis == gamepad.move_y_axis {
+            return Err(invalid("controls.gamepad.move_y_axis", "must differ from move_x_axis"));
+        }
+
+        let mut seen = Vec::new();
+        for (action, buttons) in gamepad.actions() {
+            for button in buttons {
+                if seen.contains(button) {
+                    return Err(ConfigError::Invalid {
+                        key: GamepadConfig::key_of(action).to_string(),
+                        reason: "uses a button that is already bound to another action",
+                    });
+                }
+                seen.push(*button);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(text: &str) -> String {
+        GameConfig::parse(text).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn shipped_config_loads() {
+        let config = GameConfig::parse(include_str!("../gun-score.toml")).unwrap();
+        assert_eq!(config, GameConfig::default());
+    }
+
+    #[test]
+    fn missing_keys_keep_their_defaults() {
+        let config = GameConfig::parse("[player]\nspeed = 300.0").unwrap();
+        assert_eq!(config.player.speed, 300.0);
+        assert_eq!(config.player.health, PlayerConfig::default().health);
+        assert_eq!(config.weapons, WeaponConfig::defaults());
+    }
+
+    #[test]
+    fn unknown_keys_are_refused() {
+        assert!(matches!(GameConfig::parse("[player]\nspeeed = 300.0"), Err(ConfigError::Parse(_))));
+        assert!(error("[bullet]\nspeed = 100.0\ncolor = \"000000\"").contains("unknown field `color`"));
+    }
+
+    #[test]
+    fn names_the_bad_key() {
+        assert_eq!(error("[player]\nspeed = 0.0"), "`player.speed` must be greater than 0");
+        assert_eq!(error("[window]\nwidth = 100000"), "`window.width` must be at most 4096");
+        assert_eq!(
+            error("[[weapons]]\nname = \"Pistol\"\nmagazine = 10\nreload_time = 1.0\nfire_cooldown = 0.2\nspread = 0.0\nspeed = 300.0\ndamage = [5.0, 1.0]"),
+            "`weapons[0].damage` must be written as [min, max] with min <= max",
+        );
+        assert_eq!(
+            error("[[weapons]]\nname = \"Pistol\"\nmagazine = 10\nreload_time = 1.0\nfire_cooldown = 0.2\nspread = 0.0\nspeed = 300.0\ndamage = [5.0, inf]"),
+            "`weapons[0].damage` must be written as [min, max] with two numbers",
+        );
+        assert_eq!(
+            error("[enemy.kind]\nhealth = [nan, 100.0]\ndamage = [0.0, 0.0]\nsize = 30.0\ncolor = \"549EA0\""),
+            "`enemy.kind.health` must be written as [min, max] with two numbers",
+        );
+        assert_eq!(
+            error("[enemy.charger]\nhealth = [10.0, 40.0]\ndamage = [0.0, 0.0]\nsize = 20.0\ncolor = \"orange\""),
+            "`enemy.charger.color` must be a hex color like \"F33333\"",
+        );
+        assert_eq!(
+            error("[[waves]]\ncount = 3\n[[waves]]\ncount = 0"),
+            "`waves[1].count` must be greater than 0",
+        );
+        assert_eq!(
+            error("[pickups]\nchance = 1.5"),
+            "`pickups.chance` must be between 0 and 1",
+        );
+    }
+}
@@ -0,0 +1,276 @@
+use std::collections::HashMap;
+
+use piston::input::{Button, ControllerAxisArgs, Key, MouseButton};
+use serde::{Deserialize, Serialize};
+
+use crate::config::{ControlsConfig, GamepadConfig};
+use crate::geom::{Direction};
+use crate::world::{GameInput};
+
+// how far a trigger has to be pulled before it counts as pressed.
+const TRIGGER_THRESHOLD: f64 = 0.5;
+
+// what the player wants to do, whatever button they used for it.
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Action {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Fire,
+    Reload,
+    Restart,
+    Pause,
+    NextWeapon,
+    PrevWeapon,
+    Weapon(usize), // pick the weapon in this slot, from 0
+}
+
+impl Action {
+    // movement and the trigger follow the button being held, everything else fires on press.
+    pub fn input(self, is_press: bool) -> Option<GameInput> {
+        let movement = |dir: Direction| if is_press {
+            GameInput::StartMove(dir)
+        } else {
+            GameInput::StopMove(dir)
+        };
+
+        match self {
+            Action::MoveUp => Some(movement(Direction::NORTH)),
+            Action::MoveDown => Some(movement(Direction::SOUTH)),
+            Action::MoveLeft => Some(movement(Direction::WEST)),
+            Action::MoveRight => Some(movement(Direction::EAST)),
+            Action::Fire if is_press => Some(GameInput::StartFire),
+            Action::Fire => Some(GameInput::StopFire),
+            Action::Reload if is_press => Some(GameInput::Reload),
+            Action::Restart if is_press => Some(GameInput::Restart),
+            Action::Pause if is_press => Some(GameInput::Pause),
+            Action::NextWeapon if is_press => Some(GameInput::NextWeapon),
+            Action::PrevWeapon if is_press => Some(GameInput::PrevWeapon),
+            Action::Weapon(slot) if is_press => Some(GameInput::SelectWeapon(slot)),
+            _ => None,
+        }
+    }
+}
+
+// an input event straight from the device, before it is mapped to an action.
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq)]
+pub enum RawInput {
+    Button { button: Button, pressed: bool },
+    Axis(ControllerAxisArgs),
+    Cursor([f64; 2]), // mouse position in the window
+    Scroll([f64; 2]), // mouse wheel, y > 0 is scrolling up
+}
+
+// the key/mouse/controller button -> action table, built from the `[controls]` config.
+pub struct Bindings {
+    keys: HashMap<Key, Action>,
+    mouse: HashMap<MouseButton, Action>,
+    buttons: HashMap<u8, Action>,
+    config: ControlsConfig,
+}
+
+impl Bindings {
+    pub fn new(config: &ControlsConfig) -> Self {
+        let keys = config.actions()
+            .iter()
+            .flat_map(|(action, keys)| keys.iter().map(move |key| (*key, *action)))
+            .collect();
+        let mouse = config.mouse.actions()
+            .iter()
+            .flat_map(|(action, buttons)| buttons.iter().map(move |button| (*button, *action)))
+            .collect();
+        let buttons = config.gamepad.actions()
+            .iter()
+            .flat_map(|(action, buttons)| buttons.iter().map(move |button| (*button, *action)))
+            .collect();
+
+        Bindings { keys, mouse, buttons, config: config.clone() }
+    }
+
+    pub fn action(&self, button: &Button) -> Option<Action> {
+        match button {
+            Button::Keyboard(key) => self.keys.get(key).copied(),
+            Button::Mouse(button) => self.mouse.get(button).copied(),
+            Button::Controller(controller) => self.buttons.get(&controller.button).copied(),
+            _ => None,
+        }
+    }
+
+    // turn a raw button event into something the world understands.
+    pub fn input(&self, button: &Button, is_press: bool) -> Option<GameInput> {
+        self.action(button).and_then(|action| action.input(is_press))
+    }
+
+    // the name of the first key bound to `action`, for hints on screen.
+    pub fn key_name(&self, action: Action) -> String {
+        self.config.actions()
+            .iter()
+            .find(|(bound, _)| *bound == action)
+            .and_then(|(_, keys)| keys.first())
+            .map(|key| format!("{:?}", key))
+            .unwrap_or_else(|| String::from("unbound"))
+    }
+}
+
+// ignore the inner `deadzone` of the stick and rescale the rest back to 0..1,
+// so the player can still move slowly just outside of it.
+pub fn apply_deadzone(x: f64, y: f64, deadzone: f64) -> (f64, f64) {
+    let length = (x * x + y * y).sqrt();
+    if length <= deadzone {
+        return (0.0, 0.0);
+    }
+
+    let scaled = ((length - deadzone) / (1.0 - deadzone)).min(1.0);
+    (x / length * scaled, y / length * scaled)
+}
+
+// the analog side of a controller, which needs to remember where each axis is.
+pub struct Gamepad {
+    config: GamepadConfig,
+    stick: [f64; 2],
+    moving: (f64, f64), // the last stick position sent to the world
+    trigger_down: bool,
+}
+
+impl Gamepad {
+    pub fn new(config: &GamepadConfig) -> Self {
+        Gamepad { config: config.clone(), stick: [0.0, 0.0], moving: (0.0, 0.0), trigger_down: false }
+    }
+
+    pub fn axis(&mut self, args: &ControllerAxisArgs) -> Option<GameInput> {
+        if args.axis == self.config.move_x_axis || args.axis == self.config.move_y_axis {
+            let index = if args.axis == self.config.move_x_axis { 0 } else { 1 };
+            self.stick[index] = args.position;
+
+            // a resting stick keeps sending noise, don't let it cancel keyboard movement.
+            let moving = apply_deadzone(self.stick[0], self.stick[1], self.config.deadzone);
+            if moving == self.moving {
+                return None;
+            }
+
+            self.moving = moving;
+            return Some(GameInput::Stick(moving.0, moving.1));
+        }
+
+        if self.config.fire_axes.contains(&args.axis) {
+            let down = args.position > TRIGGER_THRESHOLD;
+            let changed = down != self.trigger_down;
+            self.trigger_down = down;
+            if changed {
+                return Action::Fire.input(down);
+            }
+        }
+
+        None
+    }
+}
+
+// everything needed to turn raw device events into world inputs.
+pub struct InputMapper {
+    pub bindings: Bindings,
+    gamepad: Gamepad,
+}
+
+impl InputMapper {
+    pub fn new(config: &ControlsConfig) -> Self {
+        InputMapper {
+            bindings: Bindings::new(config),
+            gamepad: Gamepad::new(&config.gamepad),
+        }
+    }
+
+    pub fn map(&mut self, input: &RawInput) -> Option<GameInput> {
+        match input {
+            RawInput::Button { button, pressed } => self.bindings.input(button, *pressed),
+            RawInput::Axis(args) => self.gamepad.axis(args),
+            RawInput::Cursor([x, y]) if self.bindings.config.mouse.aim => Some(GameInput::Aim(*x, *y)),
+            RawInput::Cursor(_) => None,
+            // one weapon per notch, up is the next one.
+            RawInput::Scroll([_, y]) if self.bindings.config.mouse.wheel && *y != 0.0 => {
+                Some(if *y > 0.0 { GameInput::NextWeapon } else { GameInput::PrevWeapon })
+            },
+            RawInput::Scroll(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{GameConfig};
+
+    fn key(key: Key) -> Button {
+        Button::Keyboard(key)
+    }
+
+    #[test]
+    fn every_key_of_an_action_does_it() {
+        let bindings = Bindings::new(&ControlsConfig::default());
+        assert_eq!(bindings.input(&key(Key::W), true), Some(GameInput::StartMove(Direction::NORTH)));
+        assert_eq!(bindings.input(&key(Key::Up), true), Some(GameInput::StartMove(Direction::NORTH)));
+        assert_eq!(bindings.input(&key(Key::D2), true), Some(GameInput::SelectWeapon(1)));
+        assert_eq!(bindings.input(&key(Key::F12), true), None);
+    }
+
+    #[test]
+    fn held_actions_stop_on_release_the_rest_fire_on_press() {
+        assert_eq!(Action::MoveLeft.input(false), Some(GameInput::StopMove(Direction::WEST)));
+        assert_eq!(Action::Fire.input(true), Some(GameInput::StartFire));
+        assert_eq!(Action::Fire.input(false), Some(GameInput::StopFire));
+        assert_eq!(Action::Reload.input(true), Some(GameInput::Reload));
+        assert_eq!(Action::Reload.input(false), None);
+        assert_eq!(Action::Pause.input(false), None);
+    }
+
+    fn axis(axis: u8, position: f64) -> ControllerAxisArgs {
+        ControllerAxisArgs::new(0, axis, position)
+    }
+
+    #[test]
+    fn the_deadzone_is_cut_off_and_the_rest_rescaled() {
+        assert_eq!(apply_deadzone(0.1, -0.1, 0.2), (0.0, 0.0));
+        assert_eq!(apply_deadzone(0.2, 0.0, 0.2), (0.0, 0.0));
+
+        let (x, y) = apply_deadzone(0.0, 0.3, 0.2);
+        assert_eq!(x, 0.0);
+        assert!((y - 0.125).abs() < 1e-9);
+        assert_eq!(apply_deadzone(-1.0, 0.0, 0.2), (-1.0, 0.0));
+    }
+
+    #[test]
+    fn a_resting_stick_sends_nothing() {
+        let config = GamepadConfig::default();
+        let mut gamepad = Gamepad::new(&config);
+        assert_eq!(gamepad.axis(&axis(config.move_x_axis, 0.05)), None);
+        assert_eq!(gamepad.axis(&axis(config.move_y_axis, -0.1)), None);
+
+        assert!(matches!(gamepad.axis(&axis(config.move_x_axis, 1.0)), Some(GameInput::Stick(x, _)) if x > 0.9));
+        assert_eq!(gamepad.axis(&axis(config.move_x_axis, 0.0)), Some(GameInput::Stick(0.0, 0.0)));
+        assert_eq!(gamepad.axis(&axis(config.move_x_axis, 0.1)), None);
+    }
+
+    #[test]
+    fn the_trigger_fires_once_per_crossing() {
+        let config = GamepadConfig::default();
+        let trigger = config.fire_axes[0];
+        let mut gamepad = Gamepad::new(&config);
+        let inputs: Vec<Option<GameInput>> = [0.2, 0.6, 0.8, 1.0, 0.7, 0.4, 0.1, 0.9]
+            .iter()
+            .map(|position| gamepad.axis(&axis(trigger, *position)))
+            .collect();
+
+        assert_eq!(inputs, vec![
+            None, Some(GameInput::StartFire), None, None, None, Some(GameInput::StopFire), None, Some(GameInput::StartFire),
+        ]);
+    }
+
+    #[test]
+    fn a_key_bound_twice_is_refused() {
+        let error = GameConfig::parse("[controls]\nreload = [\"Space\"]").unwrap_err();
+        assert_eq!(error.to_string(), "`controls.reload` uses a key that is already bound to another action");
+
+        let error = GameConfig::parse("[controls.gamepad]\npause = [0]").unwrap_err();
+        assert_eq!(error.to_string(), "`controls.gamepad.pause` uses a button that is already bound to another action");
+    }
+}
@@ -0,0 +1,173 @@
+use crate::colors::{ColorComps};
+use crate::ecs::{Entity};
+use crate::geom::{Position, Vec2};
+use crate::schemas::enemy::{EnemyType};
+
+// where an entity is, and where it was before the last tick for render interpolation.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub struct Transform {
+    pub pos: Position,
+    pub prev_pos: Position,
+    pub angle: f64, // radians, turns the collider and the sprite
+}
+
+impl Transform {
+    pub fn new(pos: Position) -> Self {
+        Transform { pos, prev_pos: pos, angle: 0.0 }
+    }
+
+    // move without interpolating from the old position.
+    pub fn teleport(&mut self, pos: Position) {
+        self.pos = pos;
+        self.prev_pos = pos;
+    }
+
+    // `alpha` is how far (0..1) we are between the previous tick and the current one.
+    pub fn interpolate(&self, alpha: f64) -> Position {
+        self.prev_pos.lerp(&self.pos, alpha)
+    }
+}
+
+// px/sec
+#[derive(PartialEq, Copy, Clone, Debug, Default)]
+pub struct Velocity(pub Vec2);
+
+// kept inside the arena, `margin` px away from the edges.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub struct Confined {
+    pub margin: f64,
+}
+
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub struct Health {
+    pub value: f64,
+    pub max: f64,
+}
+
+impl Health {
+    pub fn new(max: f64) -> Self {
+        Health { value: max, max }
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.value <= 0.0
+    }
+}
+
+// which side an entity fights on, bullets never hurt their own team.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum Team {
+    Player,
+    Monster,
+}
+
+// seconds left before the entity disappears by itself.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub struct Lifetime(pub f64);
+
+// dealt to whatever the entity hits.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub struct Damage(pub f32);
+
+// health/sec taken off the other team's entities it touches.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub struct Contact(pub f64);
+
+// fires at the player on its own, e.g. a fighter's gun.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub struct Shooter {
+    pub cooldown: f64, // secs between two shots
+    pub ready_in: f64, // secs until the next shot
+    pub range: f64, // px
+    pub lead: bool, // aim where the target is going to be
+    pub spread: f64, // radians the shots randomly miss by, at most
+}
+
+// breaks up into `count` enemies of another type when killed.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub struct Splitter {
+    pub count: usize,
+    pub into: EnemyType,
+}
+
+// blows up when killed, touching or hitting the other team, dealing its `Damage` to everything
+// of the other team within `radius` px.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub struct Explosive {
+    pub radius: f64,
+}
+
+// a bullet that flies on through `left` more targets, never hitting one twice.
+#[derive(PartialEq, Clone, Debug)]
+pub struct Piercing {
+    pub left: u32,
+    pub hit: Vec<Entity>,
+}
+
+// bullets coming at the entity's front, up to `arc` radians off its facing, bounce off.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub struct Shield {
+    pub arc: f64,
+    pub turn: f64, // radians/sec the front turns by at most
+}
+
+impl Shield {
+    // the facing after turning from `angle` towards `target` for `dt` secs, the short way round.
+    pub fn turn_towards(&self, angle: f64, target: f64, dt: f64) -> f64 {
+        let step = self.turn * dt;
+        let diff = (target - angle + std::f64::consts::PI).rem_euclid(2.0 * std::f64::consts::PI) - std::f64::consts::PI;
+        angle + diff.clamp(-step, step)
+    }
+
+    // whether something moving along `direction` into an entity facing `angle` is blocked.
+    pub fn blocks(&self, angle: f64, direction: &Vec2) -> bool {
+        if direction.length_squared() == 0.0 {
+            return false;
+        }
+        // it hits the front when it comes against the facing.
+        let facing = Vec2::from_angle(angle, 1.0);
+        let towards = -direction.dot(&facing) / direction.length();
+        towards >= self.arc.min(std::f64::consts::PI).cos()
+    }
+}
+
+// can't be hurt for the secs left, bullets bounce off and blasts pass it by.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub struct Invulnerable(pub f64);
+
+// how to draw an entity, centered on its position.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum Renderable {
+    Icon { size: f64 }, // the gun icon, owned by the front-end
+    Square { size: f64, color: ColorComps },
+    Circle { radius: f64, color: ColorComps },
+    Rect { width: f64, height: f64, color: ColorComps },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shield_turns_the_short_way_round() {
+        let shield = Shield { arc: 2.2, turn: 1.0 };
+        assert_eq!(shield.turn_towards(0.0, 0.5, 0.1), 0.1);
+        assert!((shield.turn_towards(0.0, 0.05, 0.1) - 0.05).abs() < 1e-9);
+        // from just below half a turn to just above it is a small step, not a whole turn back
+        assert!((shield.turn_towards(3.1, -3.1, 0.1) - (3.1 + 0.0831853)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn shield_leaves_only_the_back_open() {
+        let shield = Shield { arc: 2.2, turn: 1.5 };
+        // facing right, a bullet flying left hits it head on
+        assert!(shield.blocks(0.0, &Vec2::new(-300.0, 0.0)));
+        assert!(shield.blocks(0.0, &Vec2::new(-300.0, 200.0)));
+        // from the side
+        assert!(shield.blocks(0.0, &Vec2::new(0.0, 300.0)));
+        assert!(shield.blocks(0.0, &Vec2::new(150.0, -260.0)));
+        // from behind
+        assert!(!shield.blocks(0.0, &Vec2::new(300.0, 0.0)));
+        assert!(!shield.blocks(0.0, &Vec2::new(300.0, 100.0)));
+    }
+}
@@ -0,0 +1,282 @@
+// A small entity-component-system: an entity is just an id, everything it is made of
+// lives in one `Storage` per component, and the systems work on whatever has the
+// components they need. To add a new component, give `Entities` a storage for it
+// and remove it in `despawn`.
+
+use crate::collider::{Collider};
+use crate::geom::{Position};
+use crate::schemas::player::{Player};
+use crate::schemas::enemy::{Enemy};
+use crate::schemas::boss::{Boss};
+use crate::schemas::bullet::{Bullet};
+use crate::schemas::obstacle::{Obstacle};
+use crate::schemas::explosion::{Explosion};
+use crate::schemas::pickup::{Pickup};
+
+pub mod components;
+pub mod systems;
+
+use components::{Confined, Contact, Damage, Explosive, Health, Invulnerable, Lifetime, Piercing, Renderable, Shield, Shooter, Splitter, Team, Transform, Velocity};
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Entity {
+    index: usize,
+    generation: u32, // bumped every time the index is reused, so old ids stop matching
+}
+
+impl Entity {
+    pub fn index(&self) -> usize {
+        self.index
+    }
+}
+
+// one component type for every entity, indexed by the entity's index.
+pub struct Storage<T> {
+    items: Vec<Option<(u32, T)>>,
+}
+
+impl<T> Default for Storage<T> {
+    fn default() -> Self {
+        Storage { items: Vec::new() }
+    }
+}
+
+impl<T> Storage<T> {
+    pub fn insert(&mut self, entity: Entity, item: T) {
+        if self.items.len() <= entity.index {
+            self.items.resize_with(entity.index + 1, || None);
+        }
+        self.items[entity.index] = Some((entity.generation, item));
+    }
+
+    pub fn remove(&mut self, entity: Entity) -> Option<T> {
+        match self.items.get(entity.index) {
+            Some(Some((generation, _))) if *generation == entity.generation => {
+                self.items[entity.index].take().map(|(_, item)| item)
+            },
+            _ => None,
+        }
+    }
+
+    pub fn get(&self, entity: Entity) -> Option<&T> {
+        match self.items.get(entity.index) {
+            Some(Some((generation, item))) if *generation == entity.generation => Some(item),
+            _ => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, entity: Entity) -> Option<&mut T> {
+        match self.items.get_mut(entity.index) {
+            Some(Some((generation, item))) if *generation == entity.generation => Some(item),
+            _ => None,
+        }
+    }
+
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.get(entity).is_some()
+    }
+
+    // every entity that has this component, by ascending index.
+    pub fn iter(&self) -> impl Iterator<Item = (Entity, &T)> {
+        self.items.iter().enumerate().filter_map(|(index, item)| {
+            item.as_ref().map(|(generation, item)| (Entity { index, generation: *generation }, item))
+        })
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Entity, &mut T)> {
+        self.items.iter_mut().enumerate().filter_map(|(index, item)| {
+            item.as_mut().map(|(generation, item)| (Entity { index, generation: *generation }, item))
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.iter().all(|item| item.is_none())
+    }
+}
+
+// every entity in the game and all of their components.
+#[derive(Default)]
+pub struct Entities {
+    generations: Vec<u32>,
+    alive: Vec<bool>,
+    free: Vec<usize>, // dead indices, ready to be reused
+
+    // shared components
+    pub transforms: Storage<Transform>,
+    pub velocities: Storage<Velocity>,
+    pub confined: Storage<Confined>,
+    pub colliders: Storage<Collider>,
+    pub healths: Storage<Health>,
+    pub renderables: Storage<Renderable>,
+    pub teams: Storage<Team>,
+    pub lifetimes: Storage<Lifetime>,
+    pub damages: Storage<Damage>,
+    pub contacts: Storage<Contact>,
+    pub shooters: Storage<Shooter>,
+    pub splitters: Storage<Splitter>,
+    pub explosives: Storage<Explosive>,
+    pub shields: Storage<Shield>,
+    pub piercings: Storage<Piercing>,
+    pub invulnerables: Storage<Invulnerable>,
+
+    // what kind of thing the entity is, with the state only that kind needs
+    pub players: Storage<Player>,
+    pub enemies: Storage<Enemy>,
+    pub bosses: Storage<Boss>,
+    pub bullets: Storage<Bullet>,
+    pub obstacles: Storage<Obstacle>,
+    pub explosions: Storage<Explosion>,
+    pub pickups: Storage<Pickup>,
+}
+
+impl Entities {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // a new entity without any components yet.
+    pub fn spawn(&mut self) -> Entity {
+        let index = match self.free.pop() {
+            Some(index) => index,
+            None => {
+                self.generations.push(0);
+                self.alive.push(false);
+                self.generations.len() - 1
+            },
+        };
+
+        self.alive[index] = true;
+        Entity { index, generation: self.generations[index] }
+    }
+
+    pub fn is_alive(&self, entity: Entity) -> bool {
+        self.alive.get(entity.index).copied().unwrap_or(false)
+            && self.generations[entity.index] == entity.generation
+    }
+
+    // the living entity at `index`, e.g. from a `Grid` query.
+    pub fn at(&self, index: usize) -> Option<Entity> {
+        match self.alive.get(index) {
+            Some(true) => Some(Entity { index, generation: self.generations[index] }),
+            _ => None,
+        }
+    }
+
+    // remove the entity with all of its components, does nothing if it is already gone.
+    pub fn despawn(&mut self, entity: Entity) {
+        if !self.is_alive(entity) {
+            return;
+        }
+
+        self.transforms.remove(entity);
+        self.velocities.remove(entity);
+        self.confined.remove(entity);
+        self.colliders.remove(entity);
+        self.healths.remove(entity);
+        self.renderables.remove(entity);
+        self.teams.remove(entity);
+        self.lifetimes.remove(entity);
+        self.damages.remove(entity);
+        self.contacts.remove(entity);
+        self.shooters.remove(entity);
+        self.splitters.remove(entity);
+        self.explosives.remove(entity);
+        self.shields.remove(entity);
+        self.piercings.remove(entity);
+        self.invulnerables.remove(entity);
+        self.players.remove(entity);
+        self.enemies.remove(entity);
+        self.bosses.remove(entity);
+        self.bullets.remove(entity);
+        self.obstacles.remove(entity);
+        self.explosions.remove(entity);
+        self.pickups.remove(entity);
+
+        self.alive[entity.index] = false;
+        self.generations[entity.index] = self.generations[entity.index].wrapping_add(1);
+        self.free.push(entity.index);
+    }
+
+    // nothing solid is in the way between `from` and `to`.
+    pub fn line_of_sight(&self, from: &Position, to: &Position) -> bool {
+        let ray = Collider::Circle { radius: 0.0 };
+        !self.obstacles.iter().any(|(obstacle, _)| {
+            match (self.transforms.get(obstacle), self.colliders.get(obstacle)) {
+                (Some(transform), Some(collider)) => ray.sweep(from, to, collider, &transform.pos),
+                _ => false,
+            }
+        })
+    }
+
+    // neither just respawned nor behind a shield pickup.
+    pub fn can_be_hurt(&self, entity: Entity) -> bool {
+        !self.invulnerables.contains(entity) && !self.players.get(entity).is_some_and(|player| player.is_shielded())
+    }
+
+    // whether the two entities' colliders touch right now.
+    pub fn touching(&self, a: Entity, b: Entity) -> bool {
+        let (Some(a_transform), Some(a_collider), Some(b_transform), Some(b_collider)) = (
+            self.transforms.get(a), self.colliders.get(a), self.transforms.get(b), self.colliders.get(b),
+        ) else {
+            return false;
+        };
+
+        a_collider.turned(a_transform.angle)
+            .overlaps(&a_transform.pos, &b_collider.turned(b_transform.angle), &b_transform.pos)
+    }
+
+    // like `touching`, but `a` is swept along its last move, so fast movers can't tunnel through.
+    pub fn swept_into(&self, a: Entity, b: Entity) -> bool {
+        let (Some(a_transform), Some(a_collider), Some(b_transform), Some(b_collider)) = (
+            self.transforms.get(a), self.colliders.get(a), self.transforms.get(b), self.colliders.get(b),
+        ) else {
+            return false;
+        };
+
+        let a_collider = a_collider.turned(a_transform.angle);
+        let b_collider = b_collider.turned(b_transform.angle);
+        if a_transform.prev_pos == a_transform.pos {
+            a_collider.overlaps(&a_transform.pos, &b_collider, &b_transform.pos)
+        } else {
+            a_collider.sweep(&a_transform.prev_pos, &a_transform.pos, &b_collider, &b_transform.pos)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn despawned_ids_stop_matching() {
+        let mut entities = Entities::new();
+        let first = entities.spawn();
+        entities.healths.insert(first, Health::new(10.0));
+        entities.despawn(first);
+
+        // the index is reused, but the old id must not see the new entity
+        let second = entities.spawn();
+        entities.healths.insert(second, Health::new(20.0));
+        assert_eq!(first.index(), second.index());
+        assert!(!entities.is_alive(first));
+        assert!(entities.healths.get(first).is_none());
+        assert_eq!(entities.healths.get(second).map(|health| health.value), Some(20.0));
+    }
+
+    #[test]
+    fn despawn_removes_every_component() {
+        let mut entities = Entities::new();
+        let entity = entities.spawn();
+        entities.healths.insert(entity, Health::new(10.0));
+        entities.teams.insert(entity, Team::Monster);
+        entities.despawn(entity);
+
+        assert!(entities.healths.is_empty());
+        assert!(entities.teams.is_empty());
+        assert_eq!(entities.at(entity.index()), None);
+    }
+}
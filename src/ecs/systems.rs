@@ -0,0 +1,392 @@
+// The systems, each one works on every entity that has the components it needs.
+// The game rules in `GameWorld` decide what they are run on and what the results mean.
+
+use std::f64::consts::PI;
+
+use piston::window::Size;
+
+use crate::collider::{Collider};
+use crate::ai::{lead};
+use crate::collision::{Grid};
+use crate::geom::{restrict_to_bounds, Position, Rect, Vec2};
+
+use crate::config::{BossAttack};
+use crate::schemas::boss::{Boss};
+use crate::schemas::enemy::{EnemyType};
+use crate::schemas::explosion::{Explosion};
+
+use super::{Entities, Entity};
+use super::components::{Team};
+
+// a bullet ran into something of the other team this tick.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub struct Hit {
+    pub bullet: Entity,
+    pub target: Entity,
+}
+
+// a shooter wants to fire from `from` towards `angle` (radians).
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub struct Shot {
+    pub shooter: Entity,
+    pub from: Position,
+    pub angle: f64,
+}
+
+// a boss calls `count` enemies of `enemy_type` to its side at `pos`.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub struct Summon {
+    pub boss: Entity,
+    pub pos: Position,
+    pub enemy_type: EnemyType,
+    pub count: usize,
+}
+
+// what a hit did, for the game rules to react to.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub struct Wound {
+    pub target: Entity,
+    pub team: Team, // of the bullet
+    pub amount: f64,
+    pub killed: bool,
+}
+
+// players turn towards where they want to go, enemies follow their brain and the shielded
+// ones keep their shield towards the player.
+pub fn steering(entities: &mut Entities, dt: f64) {
+    for (entity, player) in entities.players.iter_mut() {
+        if let (Some(transform), Some(velocity)) = (entities.transforms.get(entity), entities.velocities.get_mut(entity)) {
+            player.steer(transform.pos, &mut velocity.0, dt);
+        }
+        if let Some(transform) = entities.transforms.get_mut(entity) {
+            transform.angle = player.aim;
+        }
+    }
+
+    let player = entities.players.iter()
+        .find_map(|(entity, _)| entities.transforms.get(entity))
+        .map(|transform| transform.pos);
+    for (entity, enemy) in entities.enemies.iter_mut() {
+        let (Some(transform), Some(velocity)) = (entities.transforms.get_mut(entity), entities.velocities.get_mut(entity)) else {
+            continue;
+        };
+        let health = entities.healths.get(entity).map_or(1.0, |health| health.value / health.max);
+        enemy.brain.think(transform.pos, health, player, dt, &mut velocity.0);
+        if let (Some(player), Some(shield)) = (player, entities.shields.get(entity)) {
+            transform.angle = shield.turn_towards(transform.angle, (player - transform.pos).angle(), dt);
+        }
+    }
+
+    for (entity, boss) in entities.bosses.iter_mut() {
+        let (Some(transform), Some(velocity)) = (entities.transforms.get(entity), entities.velocities.get_mut(entity)) else {
+            continue;
+        };
+        let health = entities.healths.get(entity).map_or(1.0, |health| health.value / health.max);
+        boss.brain.think(transform.pos, health, player, dt, &mut velocity.0);
+    }
+}
+
+// move everything by its velocity, and keep the confined ones inside the arena.
+pub fn movement(entities: &mut Entities, dt: f64, size: Size) {
+    for (entity, velocity) in entities.velocities.iter_mut() {
+        let Some(transform) = entities.transforms.get_mut(entity) else {
+            continue;
+        };
+
+        transform.prev_pos = transform.pos;
+        transform.pos += velocity.0 * dt;
+
+        if let Some(confined) = entities.confined.get(entity) {
+            let before = transform.pos;
+            restrict_to_bounds(&mut transform.pos, [confined.margin, confined.margin, size.width, size.height]);
+            // stop pushing into a wall.
+            if transform.pos.x != before.x {
+                velocity.0.x = 0.0;
+            }
+            if transform.pos.y != before.y {
+                velocity.0.y = 0.0;
+            }
+        }
+    }
+}
+
+// shooters fire at the player once their gun is ready and the player is in range and in sight.
+// `bullet_speed` is needed to lead the shots.
+pub fn aiming(entities: &mut Entities, dt: f64, bullet_speed: f64, shots: &mut Vec<Shot>) {
+    shots.clear();
+    let Some((player, target)) = entities.players.iter()
+        .find_map(|(entity, _)| entities.transforms.get(entity).map(|transform| (entity, transform.pos)))
+    else {
+        return;
+    };
+    let velocity = entities.velocities.get(player).map_or(Vec2::zero(), |velocity| velocity.0);
+
+    let mut ready = Vec::new();
+    for (entity, shooter) in entities.shooters.iter_mut() {
+        shooter.ready_in = (shooter.ready_in - dt).max(0.0);
+        if shooter.ready_in <= 0.0 {
+            ready.push(entity);
+        }
+    }
+
+    for entity in ready {
+        let (Some(transform), Some(shooter)) = (entities.transforms.get(entity), entities.shooters.get(entity).copied()) else {
+            continue;
+        };
+        let from = transform.pos;
+        if from.distance(&target) > shooter.range || !entities.line_of_sight(&from, &target) {
+            continue;
+        }
+
+        let aim = if shooter.lead { lead(from, target, velocity, bullet_speed) } else { target };
+        if let Some(shooter) = entities.shooters.get_mut(entity) {
+            shooter.ready_in = shooter.cooldown;
+        }
+        shots.push(Shot { shooter: entity, from, angle: (aim - from).angle() });
+    }
+}
+
+// bosses switch phases as they lose health and run the attacks of the current one.
+// Their shots are added to `shots`, so run this after `aiming`.
+pub fn boss_attacks(entities: &mut Entities, dt: f64, shots: &mut Vec<Shot>, summons: &mut Vec<Summon>) {
+    summons.clear();
+    let target = entities.players.iter()
+        .find_map(|(entity, _)| entities.transforms.get(entity))
+        .map(|transform| transform.pos);
+
+    for (entity, boss) in entities.bosses.iter_mut() {
+        let Some(transform) = entities.transforms.get(entity) else {
+            continue;
+        };
+        let from = transform.pos;
+        let health = entities.healths.get(entity).map_or(1.0, |health| health.value / health.max);
+        let phase = Boss::phase_at(&boss.phases, health);
+        if phase != boss.phase {
+            boss.enter(phase);
+        }
+
+        for index in 0..boss.cooldowns.len() {
+            boss.cooldowns[index] -= dt;
+            if boss.cooldowns[index] > 0.0 {
+                continue;
+            }
+            let attack = boss.attacks()[index];
+            boss.cooldowns[index] = attack.cooldown();
+
+            match attack {
+                BossAttack::Spread { bullets, arc, .. } => {
+                    let Some(target) = target else {
+                        continue;
+                    };
+                    let aim = (target - from).angle();
+                    for bullet in 0..bullets {
+                        let offset = if bullets > 1 { arc * (bullet as f64 / (bullets - 1) as f64 - 0.5) } else { 0.0 };
+                        shots.push(Shot { shooter: entity, from, angle: aim + offset });
+                    }
+                },
+                BossAttack::Spiral { bullets, turn, .. } => {
+                    for bullet in 0..bullets {
+                        shots.push(Shot { shooter: entity, from, angle: boss.spin + 2.0 * PI * bullet as f64 / bullets as f64 });
+                    }
+                    boss.spin += turn;
+                },
+                BossAttack::Summon { count, enemy, .. } => {
+                    summons.push(Summon { boss: entity, pos: from, enemy_type: enemy, count });
+                },
+            }
+        }
+    }
+}
+
+// push everything confined back out of the walls, and stop the bullets that ran into one.
+pub fn obstacles(entities: &mut Entities) {
+    let solids: Vec<(Entity, Rect)> = entities.obstacles.iter()
+        .filter_map(|(entity, _)| {
+            let pos = entities.transforms.get(entity)?.pos;
+            match entities.colliders.get(entity)? {
+                Collider::Aabb { half } => Some((entity, Rect::new(pos.x - half.x, pos.y - half.y, half.x * 2.0, half.y * 2.0))),
+                _ => None,
+            }
+        })
+        .collect();
+    if solids.is_empty() {
+        return;
+    }
+
+    for (entity, confined) in entities.confined.iter() {
+        let (Some(transform), Some(velocity)) = (entities.transforms.get_mut(entity), entities.velocities.get_mut(entity)) else {
+            continue;
+        };
+
+        let half = Vec2::new(confined.margin, confined.margin);
+        for (_, wall) in &solids {
+            if let Some(push) = wall.push_out(&transform.pos, &half) {
+                transform.pos += push;
+                // stop pushing into the wall.
+                if push.x != 0.0 {
+                    velocity.0.x = 0.0;
+                }
+                if push.y != 0.0 {
+                    velocity.0.y = 0.0;
+                }
+            }
+        }
+    }
+
+    let stopped: Vec<Entity> = entities.bullets.iter()
+        .filter(|(bullet, _)| solids.iter().any(|(wall, _)| entities.swept_into(*bullet, *wall)))
+        .map(|(bullet, _)| bullet)
+        .collect();
+    for bullet in stopped {
+        entities.despawn(bullet);
+    }
+}
+
+// count down lifetimes and remove whatever ran out, invulnerability wears off the same way.
+pub fn lifetime(entities: &mut Entities, dt: f64) {
+    let mut expired = Vec::new();
+    for (entity, lifetime) in entities.lifetimes.iter_mut() {
+        lifetime.0 -= dt;
+        if lifetime.0 <= 0.0 {
+            expired.push(entity);
+        }
+    }
+
+    for entity in expired {
+        entities.despawn(entity);
+    }
+
+    let mut worn_off = Vec::new();
+    for (entity, invulnerable) in entities.invulnerables.iter_mut() {
+        invulnerable.0 -= dt;
+        if invulnerable.0 <= 0.0 {
+            worn_off.push(entity);
+        }
+    }
+
+    for entity in worn_off {
+        entities.invulnerables.remove(entity);
+    }
+}
+
+// find the first living target of the other team each bullet ran into, along its whole path this tick.
+pub fn collision(entities: &Entities, grid: &mut Grid, hits: &mut Vec<Hit>) {
+    hits.clear();
+    grid.clear();
+    for (entity, _) in entities.healths.iter() {
+        if let (Some(transform), Some(collider)) = (entities.transforms.get(entity), entities.colliders.get(entity)) {
+            grid.insert(entity.index(), &transform.pos, collider.bounding_radius());
+        }
+    }
+
+    let mut nearby = Vec::new();
+    for (bullet, _) in entities.bullets.iter() {
+        let (Some(transform), Some(collider), Some(team)) = (
+            entities.transforms.get(bullet), entities.colliders.get(bullet), entities.teams.get(bullet),
+        ) else {
+            continue;
+        };
+
+        let center = transform.prev_pos.lerp(&transform.pos, 0.5);
+        let reach = transform.prev_pos.distance(&transform.pos) / 2.0 + collider.bounding_radius();
+        grid.query(&center, reach, &mut nearby);
+
+        let pierced = entities.piercings.get(bullet).map_or(&[][..], |piercing| &piercing.hit);
+        let target = nearby.iter()
+            .filter_map(|index| entities.at(*index))
+            .find(|target| {
+                !pierced.contains(target)
+                    && entities.teams.get(*target).is_some_and(|other| other != team)
+                    && entities.healths.get(*target).is_some_and(|health| !health.is_dead())
+                    && entities.swept_into(bullet, *target)
+            });
+        if let Some(target) = target {
+            hits.push(Hit { bullet, target });
+        }
+    }
+}
+
+// take the bullets' damage off their targets' health, every bullet is used up by its hit
+// unless it still pierces. A bullet that runs into a shield is used up without doing any damage,
+// an explosive one blows up instead.
+pub fn damage(entities: &mut Entities, hits: &[Hit], wounds: &mut Vec<Wound>) {
+    wounds.clear();
+    for hit in hits {
+        let amount = entities.damages.get(hit.bullet).map_or(0.0, |damage| f64::from(damage.0));
+        let team = entities.teams.get(hit.bullet).copied().unwrap_or(Team::Monster);
+        // another bullet already finished it off this tick, this one flies on.
+        if entities.healths.get(hit.target).is_none_or(|health| health.is_dead()) {
+            continue;
+        }
+
+        let blocked = match (entities.shields.get(hit.target), entities.transforms.get(hit.target), entities.velocities.get(hit.bullet)) {
+            (Some(shield), Some(transform), Some(velocity)) => shield.blocks(transform.angle, &velocity.0),
+            _ => false,
+        };
+        if blocked || !entities.can_be_hurt(hit.target) {
+            entities.despawn(hit.bullet);
+            continue;
+        }
+        if entities.explosives.contains(hit.bullet) {
+            explode(entities, hit.bullet, wounds);
+            continue;
+        }
+        let Some(health) = entities.healths.get_mut(hit.target) else {
+            continue;
+        };
+
+        health.value -= amount;
+        wounds.push(Wound { target: hit.target, team, amount, killed: health.is_dead() });
+        match entities.piercings.get_mut(hit.bullet) {
+            Some(piercing) if piercing.left > 0 => {
+                piercing.left -= 1;
+                piercing.hit.push(hit.target);
+            },
+            _ => entities.despawn(hit.bullet),
+        }
+    }
+}
+
+// everything of the other team the explosive's radius reaches takes its damage, then it's gone.
+pub fn explode(entities: &mut Entities, entity: Entity, wounds: &mut Vec<Wound>) {
+    let (Some(explosive), Some(transform), Some(team)) = (
+        entities.explosives.get(entity).copied(), entities.transforms.get(entity).copied(), entities.teams.get(entity).copied(),
+    ) else {
+        return;
+    };
+    let amount = entities.damages.get(entity).map_or(0.0, |damage| f64::from(damage.0));
+
+    let caught: Vec<Entity> = entities.healths.iter()
+        .filter(|(other, health)| {
+            !health.is_dead()
+                && entities.can_be_hurt(*other)
+                && entities.teams.get(*other).is_some_and(|other| *other != team)
+                && entities.transforms.get(*other).is_some_and(|other_transform| {
+                    let size = entities.colliders.get(*other).map_or(0.0, |collider| collider.bounding_radius());
+                    other_transform.pos.distance(&transform.pos) <= explosive.radius + size
+                })
+        })
+        .map(|(other, _)| other)
+        .collect();
+    for target in caught {
+        if let Some(health) = entities.healths.get_mut(target) {
+            health.value -= amount;
+            wounds.push(Wound { target, team, amount, killed: health.is_dead() });
+        }
+    }
+
+    entities.despawn(entity);
+    Explosion::spawn(entities, transform.pos, explosive.radius);
+}
+
+// remove everything that died, except players, who have lives to lose instead.
+pub fn reap(entities: &mut Entities) {
+    let dead: Vec<Entity> = entities.healths.iter()
+        .filter(|(entity, health)| health.is_dead() && !entities.players.contains(*entity))
+        .map(|(entity, _)| entity)
+        .collect();
+
+    for entity in dead {
+        entities.despawn(entity);
+    }
+}
@@ -1,6 +1,11 @@
 // source: https://github.com/a5huynh/defender-game/blob/master/src/geom.rs
 
-#[derive(Copy, Clone, PartialEq)]
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+
+use serde::{Deserialize, Serialize};
+
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Copy, Clone, PartialEq, Debug)]
 pub enum Direction {
     WEST,
     NORTH,
@@ -8,15 +13,204 @@ pub enum Direction {
     SOUTH
 }
 
-#[derive(PartialEq, Copy, Clone)]
-pub struct Position {
+impl Direction {
+    // radians, with y pointing down like the screen.
+    pub fn angle(&self) -> f64 {
+        match self {
+            Direction::EAST => 0.0,
+            Direction::SOUTH => std::f64::consts::FRAC_PI_2,
+            Direction::WEST => std::f64::consts::PI,
+            Direction::NORTH => -std::f64::consts::FRAC_PI_2,
+        }
+    }
+
+    // one step in this direction.
+    pub fn offset(&self) -> Vec2 {
+        match self {
+            Direction::EAST => Vec2::new(1.0, 0.0),
+            Direction::SOUTH => Vec2::new(0.0, 1.0),
+            Direction::WEST => Vec2::new(-1.0, 0.0),
+            Direction::NORTH => Vec2::new(0.0, -1.0),
+        }
+    }
+}
+
+// a 2d vector, used for positions, velocities and directions alike.
+#[derive(PartialEq, Copy, Clone, Debug, Default)]
+pub struct Vec2 {
     pub x: f64,
     pub y: f64
 }
 
-impl Position {
-    pub fn new(x: f64, y: f64) -> Position {
-        Position { x, y }
+// a point in the arena.
+pub type Position = Vec2;
+
+impl Vec2 {
+    pub fn new(x: f64, y: f64) -> Vec2 {
+        Vec2 { x, y }
+    }
+
+    pub fn zero() -> Vec2 {
+        Vec2::new(0.0, 0.0)
+    }
+
+    // a vector of `length` pointing at `angle` (radians, y pointing down).
+    pub fn from_angle(angle: f64, length: f64) -> Vec2 {
+        Vec2::new(angle.cos() * length, angle.sin() * length)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.x == 0.0 && self.y == 0.0
+    }
+
+    pub fn length(&self) -> f64 {
+        self.x.hypot(self.y)
+    }
+
+    pub fn length_squared(&self) -> f64 {
+        self.dot(self)
+    }
+
+    pub fn distance(&self, to: &Vec2) -> f64 {
+        (*to - *self).length()
+    }
+
+    pub fn scale(&self, factor: f64) -> Vec2 {
+        Vec2::new(self.x * factor, self.y * factor)
+    }
+
+    // same direction, length 1. The zero vector stays zero.
+    pub fn normalize(&self) -> Vec2 {
+        let length = self.length();
+        if length == 0.0 {
+            Vec2::zero()
+        } else {
+            self.scale(1.0 / length)
+        }
+    }
+
+    pub fn dot(&self, other: &Vec2) -> f64 {
+        self.x * other.x + self.y * other.y
+    }
+
+    // the point `t` (0..1) of the way from `self` to `to`.
+    pub fn lerp(&self, to: &Vec2, t: f64) -> Vec2 {
+        *self + (*to - *self).scale(t)
+    }
+
+    // radians, 0 is east and y points down.
+    pub fn angle(&self) -> f64 {
+        self.y.atan2(self.x)
+    }
+
+    pub fn rotate(&self, angle: f64) -> Vec2 {
+        let (sin, cos) = angle.sin_cos();
+        Vec2::new(self.x * cos - self.y * sin, self.x * sin + self.y * cos)
+    }
+
+    // step from `self` towards `to`, but never further than `max`.
+    pub fn move_towards(&self, to: &Vec2, max: f64) -> Vec2 {
+        let gap = *to - *self;
+        let length = gap.length();
+        if length <= max {
+            *to
+        } else {
+            *self + gap.scale(max / length)
+        }
+    }
+}
+
+impl Add for Vec2 {
+    type Output = Vec2;
+
+    fn add(self, other: Vec2) -> Vec2 {
+        Vec2::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+impl Sub for Vec2 {
+    type Output = Vec2;
+
+    fn sub(self, other: Vec2) -> Vec2 {
+        Vec2::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+impl Mul<f64> for Vec2 {
+    type Output = Vec2;
+
+    fn mul(self, factor: f64) -> Vec2 {
+        self.scale(factor)
+    }
+}
+
+impl Neg for Vec2 {
+    type Output = Vec2;
+
+    fn neg(self) -> Vec2 {
+        Vec2::new(-self.x, -self.y)
+    }
+}
+
+impl AddAssign for Vec2 {
+    fn add_assign(&mut self, other: Vec2) {
+        *self = *self + other;
+    }
+}
+
+impl SubAssign for Vec2 {
+    fn sub_assign(&mut self, other: Vec2) {
+        *self = *self - other;
+    }
+}
+
+// an axis-aligned rectangle, `x` and `y` are its top left corner.
+#[derive(Serialize, Deserialize, PartialEq, Copy, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct Rect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Rect {
+    pub fn new(x: f64, y: f64, width: f64, height: f64) -> Self {
+        Rect { x, y, width, height }
+    }
+
+    pub fn center(&self) -> Position {
+        Position::new(self.x + self.width / 2.0, self.y + self.height / 2.0)
+    }
+
+    pub fn half(&self) -> Vec2 {
+        Vec2::new(self.width / 2.0, self.height / 2.0)
+    }
+
+    pub fn contains(&self, pos: &Position) -> bool {
+        (self.x..=self.x + self.width).contains(&pos.x) && (self.y..=self.y + self.height).contains(&pos.y)
+    }
+
+    // lies completely inside an arena of `width` x `height`.
+    pub fn fits_in(&self, width: f64, height: f64) -> bool {
+        self.x >= 0.0 && self.y >= 0.0 && self.x + self.width <= width && self.y + self.height <= height
+    }
+
+    // the shortest move that gets a box of `half` size at `pos` out of this rect,
+    // or nothing when they don't overlap.
+    pub fn push_out(&self, pos: &Position, half: &Vec2) -> Option<Vec2> {
+        let delta = *pos - self.center();
+        let own = self.half();
+        let overlap = Vec2::new(half.x + own.x - delta.x.abs(), half.y + own.y - delta.y.abs());
+        if overlap.x <= 0.0 || overlap.y <= 0.0 {
+            return None;
+        }
+
+        if overlap.x < overlap.y {
+            Some(Vec2::new(overlap.x.copysign(delta.x), 0.0))
+        } else {
+            Some(Vec2::new(0.0, overlap.y.copysign(delta.y)))
+        }
     }
 }
 
@@ -25,15 +219,73 @@ pub fn restrict_to_bounds(pos: &mut Position, bounds: [f64; 4]) {
     // Make sure movement is within the window bounds.
     if pos.x - bounds[0] <= 0.0 {
         pos.x = bounds[0];
-    } else if pos.x + bounds[0] >= bounds[2] as f64 {
-        pos.x = bounds[2] as f64 - bounds[0]
+    } else if pos.x + bounds[0] >= bounds[2] {
+        pos.x = bounds[2] - bounds[0]
     }
 
     if pos.y - bounds[1] <= 0.0 {
         pos.y = bounds[1];
-    } else if pos.y + bounds[1] >= bounds[3] as f64 {
-        pos.y = bounds[3] as f64 - bounds[1];
+    } else if pos.y + bounds[1] >= bounds[3] {
+        pos.y = bounds[3] - bounds[1];
     }
 
 //    println!("After: x: {}, y: {}", pos.x, pos.y);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f64 = 1e-9;
+
+    fn close(a: Vec2, b: Vec2) -> bool {
+        (a - b).length() < EPSILON
+    }
+
+    #[test]
+    fn arithmetic() {
+        let a = Vec2::new(1.0, 2.0);
+        let b = Vec2::new(3.0, -4.0);
+        assert_eq!(a + b, Vec2::new(4.0, -2.0));
+        assert_eq!(a - b, Vec2::new(-2.0, 6.0));
+        assert_eq!(a * 2.0, Vec2::new(2.0, 4.0));
+        assert_eq!(-a, Vec2::new(-1.0, -2.0));
+        assert_eq!(a.dot(&b), -5.0);
+        assert_eq!(b.length(), 5.0);
+        assert_eq!(a.distance(&b), 40.0_f64.sqrt());
+    }
+
+    #[test]
+    fn normalize() {
+        assert!(close(Vec2::new(3.0, 4.0).normalize(), Vec2::new(0.6, 0.8)));
+        assert_eq!(Vec2::zero().normalize(), Vec2::zero());
+    }
+
+    #[test]
+    fn lerp_and_move_towards() {
+        let a = Vec2::new(0.0, 0.0);
+        let b = Vec2::new(10.0, 0.0);
+        assert_eq!(a.lerp(&b, 0.25), Vec2::new(2.5, 0.0));
+        assert_eq!(a.move_towards(&b, 4.0), Vec2::new(4.0, 0.0));
+        assert_eq!(a.move_towards(&b, 40.0), b);
+    }
+
+    #[test]
+    fn push_out_of_rect() {
+        let wall = Rect::new(0.0, 0.0, 100.0, 20.0);
+        let half = Vec2::new(5.0, 5.0);
+        // barely into the bottom side, pushed back down
+        assert_eq!(wall.push_out(&Vec2::new(50.0, 23.0), &half), Some(Vec2::new(0.0, 2.0)));
+        // into the left end, pushed out to the left
+        assert_eq!(wall.push_out(&Vec2::new(-3.0, 10.0), &half), Some(Vec2::new(-2.0, 0.0)));
+        assert_eq!(wall.push_out(&Vec2::new(50.0, 26.0), &half), None);
+    }
+
+    #[test]
+    fn angles() {
+        let north = Direction::NORTH.offset();
+        assert!((north.angle() - Direction::NORTH.angle()).abs() < EPSILON);
+        assert!(close(Vec2::from_angle(north.angle(), 2.0), north * 2.0));
+        assert!(close(Vec2::new(1.0, 0.0).rotate(std::f64::consts::FRAC_PI_2), Vec2::new(0.0, 1.0)));
+    }
+}
@@ -0,0 +1,243 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::{ConfigError, GameConfig, SpawnPattern, WaveConfig};
+use crate::geom::{Position, Rect};
+
+// px, the largest arena on either side: it is also the window's size.
+pub const MAX_SIZE: f64 = 4096.0;
+
+// an arena to fight in, loaded from a TOML file:
+//
+//     name = "Pillars"
+//     width = 800.0
+//     height = 600.0
+//     player_start = [100.0, 300.0]
+//
+//     [[obstacles]] # walls that stop movement and bullets
+//     x = 380.0
+//     y = 200.0
+//     width = 40.0
+//     height = 200.0
+//
+//     [[spawn_zones]] # used by waves with `pattern = "zones"`
+//     x = 650.0
+//     y = 50.0
+//     width = 100.0
+//     height = 500.0
+//
+//     [[waves]] # same as in the config, and replacing those when given
+//     count = 6
+//     mix = { kind = 2.0, charger = 1.0 }
+//     pattern = "zones"
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Level {
+    #[serde(default)]
+    pub name: String,
+    pub width: f64,
+    pub height: f64,
+    pub player_start: [f64; 2],
+    #[serde(default)]
+    pub obstacles: Vec<Rect>,
+    #[serde(default)]
+    pub spawn_zones: Vec<Rect>,
+    #[serde(default)]
+    pub waves: Vec<WaveConfig>, // empty means the config's waves are played
+}
+
+#[derive(Debug)]
+pub enum LevelError {
+    Io(io::Error),
+    Parse(toml::de::Error),
+    Invalid { key: String, reason: String },
+}
+
+impl fmt::Display for LevelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LevelError::Io(err) => write!(f, "unable to read the level file: {}", err),
+            LevelError::Parse(err) => write!(f, "the level file is broken: {}", err),
+            LevelError::Invalid { key, reason } => write!(f, "`{}` {}", key, reason),
+        }
+    }
+}
+
+impl std::error::Error for LevelError {}
+
+impl From<io::Error> for LevelError {
+    fn from(err: io::Error) -> Self {
+        LevelError::Io(err)
+    }
+}
+
+impl From<toml::de::Error> for LevelError {
+    fn from(err: toml::de::Error) -> Self {
+        LevelError::Parse(err)
+    }
+}
+
+impl From<ConfigError> for LevelError {
+    fn from(err: ConfigError) -> Self {
+        match err {
+            ConfigError::Io(err) => LevelError::Io(err),
+            ConfigError::Parse(err) => LevelError::Parse(err),
+            ConfigError::Invalid { key, reason } => invalid(key, reason),
+        }
+    }
+}
+
+fn invalid(key: String, reason: &str) -> LevelError {
+    LevelError::Invalid { key, reason: reason.to_string() }
+}
+
+// every rect must have a size and lie inside the arena.
+fn check_rects(key: &str, rects: &[Rect], width: f64, height: f64) -> Result<(), LevelError> {
+    for (index, rect) in rects.iter().enumerate() {
+        if !(rect.width > 0.0 && rect.height > 0.0) {
+            return Err(invalid(format!("{}[{}]", key, index), "must have a width and height greater than 0"));
+        }
+        if !rect.fits_in(width, height) {
+            return Err(invalid(format!("{}[{}]", key, index), "must lie inside the arena"));
+        }
+    }
+
+    Ok(())
+}
+
+impl Level {
+    // the empty field of the window's size, played when no level is given.
+    pub fn open_field(config: &GameConfig) -> Self {
+        Level {
+            name: String::from("Open field"),
+            width: f64::from(config.window.width),
+            height: f64::from(config.window.height),
+            player_start: [0.0, 0.0],
+            obstacles: Vec::new(),
+            spawn_zones: Vec::new(),
+            waves: Vec::new(),
+        }
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Level, LevelError> {
+        Level::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn parse(text: &str) -> Result<Level, LevelError> {
+        let level: Level = toml::from_str(text)?;
+        level.validate()?;
+        Ok(level)
+    }
+
+    pub fn validate(&self) -> Result<(), LevelError> {
+        for (key, value) in [("width", self.width), ("height", self.height)] {
+            if !(value > 0.0 && value.is_finite()) {
+                return Err(invalid(key.to_string(), "must be greater than 0"));
+            }
+            if value > MAX_SIZE {
+                return Err(invalid(key.to_string(), "must be at most 4096"));
+            }
+        }
+
+        check_rects("obstacles", &self.obstacles, self.width, self.height)?;
+        check_rects("spawn_zones", &self.spawn_zones, self.width, self.height)?;
+
+        let start = self.start();
+        if !Rect::new(0.0, 0.0, self.width, self.height).contains(&start) {
+            return Err(invalid(String::from("player_start"), "must lie inside the arena"));
+        }
+        if let Some(index) = self.obstacles.iter().position(|obstacle| obstacle.contains(&start)) {
+            return Err(invalid(String::from("player_start"), &format!("is inside obstacles[{}]", index)));
+        }
+
+        for (index, wave) in self.waves.iter().enumerate() {
+            wave.validate(&format!("waves[{}]", index))?;
+            if wave.pattern == SpawnPattern::Zones && self.spawn_zones.is_empty() {
+                return Err(invalid(format!("waves[{}].pattern", index), "is \"zones\", but the level has no spawn_zones"));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn start(&self) -> Position {
+        Position::new(self.player_start[0], self.player_start[1])
+    }
+
+    // the level's own waves, or the config's when it has none.
+    pub fn waves<'a>(&'a self, config: &'a GameConfig) -> &'a [WaveConfig] {
+        if self.waves.is_empty() {
+            &config.waves
+        } else {
+            &self.waves
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PILLARS: &str = r#"
+        name = "Pillars"
+        width = 800.0
+        height = 600.0
+        player_start = [100.0, 300.0]
+
+        [[obstacles]]
+        x = 380.0
+        y = 200.0
+        width = 40.0
+        height = 200.0
+
+        [[spawn_zones]]
+        x = 650.0
+        y = 50.0
+        width = 100.0
+        height = 500.0
+
+        [[waves]]
+        count = 6
+        pattern = "zones"
+    "#;
+
+    fn error(text: &str) -> String {
+        Level::parse(text).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn parses_a_level() {
+        let level = Level::parse(PILLARS).unwrap();
+        assert_eq!(level.obstacles, vec![Rect::new(380.0, 200.0, 40.0, 200.0)]);
+        assert_eq!(level.waves.len(), 1);
+        assert_eq!(level.waves[0].pattern, SpawnPattern::Zones);
+        assert_eq!(level.start(), Position::new(100.0, 300.0));
+    }
+
+    #[test]
+    fn shipped_levels_load() {
+        Level::parse(include_str!("../levels/pillars.toml")).unwrap();
+    }
+
+    #[test]
+    fn points_at_the_broken_entry() {
+        assert_eq!(
+            error(&PILLARS.replace("player_start = [100.0, 300.0]", "player_start = [400.0, 300.0]")),
+            "`player_start` is inside obstacles[0]",
+        );
+        assert_eq!(
+            error(&PILLARS.replace("x = 650.0", "x = 750.0")),
+            "`spawn_zones[0]` must lie inside the arena",
+        );
+        assert_eq!(
+            error(&PILLARS.replace("count = 6", "count = 6\nmix = { kind = 1.0, charger = -2.0 }")),
+            "`waves[0].mix.charger` must not be below 0",
+        );
+        assert!(error(&PILLARS.replace("width = 800.0", "")).contains("missing field `width`"));
+        assert_eq!(error(&PILLARS.replace("width = 800.0", "width = 1e7")), "`width` must be at most 4096");
+    }
+}
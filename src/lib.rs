@@ -0,0 +1,23 @@
+extern crate glutin_window;
+extern crate graphics;
+extern crate opengl_graphics;
+extern crate piston;
+
+pub mod util;
+pub mod geom;
+pub mod collider;
+pub mod collision;
+pub mod schemas;
+pub mod ecs;
+pub mod config;
+pub mod level;
+pub mod ai;
+pub mod weapon;
+pub mod waves;
+pub mod world;
+pub mod controls;
+pub mod replay;
+pub mod textures;
+pub mod colors;
+pub mod render;
+pub mod app;
@@ -1,22 +1,87 @@
-extern crate glutin_window;
-extern crate graphics;
-extern crate opengl_graphics;
+extern crate gun_score;
 extern crate piston;
 
-use piston::input::{RenderEvent, UpdateEvent, ReleaseEvent, PressEvent};
+use std::path::Path;
+use std::process;
 
-mod util;
-mod geom;
-mod schemas;
-mod textures;
-mod colors;
-mod app;
+use piston::input::{RenderEvent, UpdateEvent, ReleaseEvent, PressEvent, ControllerAxisEvent, MouseCursorEvent, MouseScrollEvent};
 
-use app::{GunScoreApp};
+mod cli;
+
+use gun_score::app::{GunScoreApp};
+use gun_score::config::{GameConfig};
+use gun_score::level::{Level};
+use gun_score::replay::{Replay};
+use gun_score::util::{random_seed};
 use piston::event_loop::{EventSettings, Events};
 
+// picked up from the working directory when no `--config` is given.
+const DEFAULT_CONFIG: &str = "gun-score.toml";
+
+fn load_config(path: Option<&str>) -> GameConfig {
+    let path = match path {
+        Some(path) => path,
+        None if Path::new(DEFAULT_CONFIG).exists() => DEFAULT_CONFIG,
+        None => return GameConfig::default(),
+    };
+
+    GameConfig::load(path).unwrap_or_else(|err| {
+        eprintln!("{}: {}", path, err);
+        process::exit(1);
+    })
+}
+
+// the open field of the window's size when no `--level` is given.
+fn load_level(path: Option<&str>, config: &GameConfig) -> Level {
+    let Some(path) = path else {
+        return Level::open_field(config);
+    };
+
+    Level::load(path).unwrap_or_else(|err| {
+        eprintln!("{}: {}", path, err);
+        process::exit(1);
+    })
+}
+
+fn load_replay(path: &str) -> Replay {
+    Replay::load(path).unwrap_or_else(|err| {
+        eprintln!("{}: {}", path, err);
+        process::exit(1);
+    })
+}
+
 fn main() {
-    let mut app = GunScoreApp::new();
+    let options = cli::parse_args();
+
+    // headless check of a recorded run.
+    if let Some(path) = &options.verify {
+        let replay = load_replay(path);
+        let world = replay.simulate();
+        println!("seed: {}, ticks: {}, score: {}, status: {:?}",
+                 replay.seed, replay.ticks, world.player().score.floor(), world.status);
+        return;
+    }
+
+    // a replay always brings the config, level and seed it was recorded with.
+    let replay = options.replay.as_deref().map(load_replay);
+    let (config, level, seed) = match &replay {
+        Some(replay) => (replay.config.clone(), replay.level.clone(), replay.seed),
+        None => {
+            let config = load_config(options.config.as_deref());
+            let level = load_level(options.level.as_deref(), &config);
+            (config, level, options.seed.unwrap_or_else(random_seed))
+        },
+    };
+    // print it, so a run can be reproduced with `--seed`.
+    println!("seed: {}", seed);
+
+    let mut app = GunScoreApp::new(config, level, seed);
+    if let Some(replay) = replay {
+        app.play(replay);
+    } else if options.record.is_some() {
+        app.start_recording();
+    }
+
     let mut events = Events::new(EventSettings::new());
     while let Some(e) = events.next(&mut app.window) {
         if let Some(i) = e.press_args() {
@@ -27,6 +92,18 @@ fn main() {
             app.input(&i, false);
         }
 
+        if let Some(pos) = e.mouse_cursor_args() {
+            app.cursor(pos);
+        }
+
+        if let Some(scroll) = e.mouse_scroll_args() {
+            app.scroll(scroll);
+        }
+
+        if let Some(args) = e.controller_axis_args() {
+            app.axis(&args);
+        }
+
         if let Some(args) = e.render_args() {
             app.render(&args);
         }
@@ -35,5 +112,12 @@ fn main() {
             app.update(&args);
         }
     }
-}
 
+    if let (Some(path), Some(recording)) = (&options.record, app.take_recording()) {
+        if let Err(err) = recording.save(path) {
+            eprintln!("{}: {}", path, err);
+            process::exit(1);
+        }
+        println!("replay saved to {}", path);
+    }
+}
@@ -0,0 +1,64 @@
+use graphics::{Context, Transformed, ellipse, line, rectangle};
+use opengl_graphics::{GlGraphics};
+
+use crate::colors::{Colors};
+use crate::ecs::{Entities};
+use crate::ecs::components::{Renderable};
+use crate::textures::{IconStruct};
+
+// times/sec an entity that can't be hurt blinks.
+const BLINK_RATE: f64 = 6.0;
+
+// drawing lives here so the game objects themselves never need a GL context.
+// `alpha` is how far (0..1) we are between the previous tick and the current one.
+pub fn render_entities(entities: &Entities, icon: &IconStruct, alpha: f64, ctx: &Context, gl: &mut GlGraphics) {
+    for (entity, renderable) in entities.renderables.iter() {
+        let Some(transform) = entities.transforms.get(entity) else {
+            continue;
+        };
+        let pos = transform.interpolate(alpha);
+        // hidden for half of every blink.
+        if entities.invulnerables.get(entity).is_some_and(|invulnerable| (invulnerable.0 * BLINK_RATE * 2.0).floor() as u64 % 2 == 1) {
+            continue;
+        }
+
+        match *renderable {
+            // the gun icon, which is owned by the front-end.
+            Renderable::Icon { size } => {
+                let radius = size / 2.0;
+                let mut transformer = ctx.transform
+                    .trans(pos.x, pos.y)
+                    .rot_rad(transform.angle)
+                    .trans(-radius, -radius);
+
+                // flip the gun when it points to the left, so it isn't upside down
+                if transform.angle.cos() < 0.0 {
+                    transformer = transformer.flip_v();
+                }
+
+                icon.img.draw(&icon.texture, &ctx.draw_state, transformer, gl);
+            },
+            Renderable::Square { size, color } => {
+                // TODO: change the enemy's body to image.
+                let radius = size / 2.0;
+                let transformer = ctx.transform.trans(pos.x, pos.y)
+                    .trans(-radius, -radius);
+                rectangle(color, rectangle::square(0.0, 0.0, size), transformer, gl);
+
+                // a bar along the shielded side.
+                if entities.shields.contains(entity) {
+                    let transformer = ctx.transform.trans(pos.x, pos.y).rot_rad(transform.angle);
+                    line(Colors::init().black, 3.0, [radius + 4.0, -radius, radius + 4.0, radius], transformer, gl);
+                }
+            },
+            Renderable::Rect { width, height, color } => {
+                rectangle(color, [-width / 2.0, -height / 2.0, width, height], ctx.transform.trans(pos.x, pos.y), gl);
+            },
+            Renderable::Circle { radius, color } => {
+                let transformer = ctx.transform
+                    .trans(pos.x, pos.y);
+                ellipse(color, [-radius, -radius, radius * 2.0, radius * 2.0], transformer, gl);
+            },
+        }
+    }
+}
@@ -0,0 +1,257 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::{ConfigError, GameConfig};
+use crate::controls::{InputMapper, RawInput};
+use crate::level::{Level, LevelError};
+use crate::world::{GameWorld, TICK_DT};
+
+// bump this whenever the file layout or the meaning of a tick changes.
+pub const REPLAY_VERSION: u32 = 20;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ReplayEvent {
+    pub tick: usize, // the tick this input was fed into
+    pub input: RawInput,
+}
+
+// everything needed to play a run again: the seed, the config and level it was played with,
+// how many ticks (of `TICK_DT`) it lasted and every button and stick movement.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Replay {
+    pub version: u32,
+    pub seed: u64,
+    pub config: GameConfig,
+    pub level: Level,
+    pub ticks: usize,
+    pub events: Vec<ReplayEvent>,
+}
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Io(io::Error),
+    Format(serde_json::Error),
+    Version(u32),
+    Config(ConfigError),
+    Level(LevelError),
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Io(err) => write!(f, "unable to access the replay file: {}", err),
+            ReplayError::Format(err) => write!(f, "the replay file is broken: {}", err),
+            ReplayError::Version(version) => write!(
+                f, "replay version {} is not supported (expected {})", version, REPLAY_VERSION
+            ),
+            ReplayError::Config(err) => write!(f, "the replay has a bad config: {}", err),
+            ReplayError::Level(err) => write!(f, "the replay has a bad level: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl From<io::Error> for ReplayError {
+    fn from(err: io::Error) -> Self {
+        ReplayError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for ReplayError {
+    fn from(err: serde_json::Error) -> Self {
+        ReplayError::Format(err)
+    }
+}
+
+impl Replay {
+    pub fn new(seed: u64, config: GameConfig, level: Level) -> Self {
+        Replay {
+            version: REPLAY_VERSION,
+            seed,
+            config,
+            level,
+            ticks: 0,
+            events: Vec::new(),
+        }
+    }
+
+    // inputs always belong to the tick that hasn't run yet.
+    pub fn record_input(&mut self, input: RawInput) {
+        self.events.push(ReplayEvent { tick: self.ticks, input });
+    }
+
+    pub fn record_tick(&mut self) {
+        self.ticks += 1;
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), ReplayError> {
+        let writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer(writer, self)?;
+        Ok(())
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Replay, ReplayError> {
+        let reader = BufReader::new(File::open(path)?);
+        // check the version first, an old file won't match the current layout anyway.
+        let value: serde_json::Value = serde_json::from_reader(reader)?;
+        let version = value.get("version").and_then(|version| version.as_u64()).unwrap_or(0) as u32;
+        if version != REPLAY_VERSION {
+            return Err(ReplayError::Version(version));
+        }
+
+        let replay: Replay = serde_json::from_value(value)?;
+        replay.config.validate().map_err(ReplayError::Config)?;
+        replay.level.validate().map_err(ReplayError::Level)?;
+
+        Ok(replay)
+    }
+
+    pub fn new_world(&self) -> GameWorld {
+        GameWorld::new(self.config.clone(), self.level.clone(), self.seed)
+    }
+
+    // play the whole run without a window, e.g. to check a reported score.
+    pub fn simulate(&self) -> GameWorld {
+        let mut world = self.new_world();
+        let mut playback = Playback::new(self.clone());
+        while playback.step(&mut world) {}
+
+        world
+    }
+}
+
+// feeds a replay back into a world, one tick at a time.
+pub struct Playback {
+    replay: Replay,
+    mapper: InputMapper, // the controls the run was recorded with
+    tick: usize,
+    next_event: usize,
+}
+
+impl Playback {
+    pub fn new(replay: Replay) -> Self {
+        let mapper = InputMapper::new(&replay.config.controls);
+        Playback { replay, mapper, tick: 0, next_event: 0 }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.tick >= self.replay.ticks
+    }
+
+    // run the next recorded tick, returns false once the replay is over.
+    pub fn step(&mut self, world: &mut GameWorld) -> bool {
+        if self.is_finished() {
+            return false;
+        }
+
+        let mut inputs = Vec::new();
+        while let Some(event) = self.replay.events.get(self.next_event) {
+            if event.tick != self.tick {
+                break;
+            }
+
+            if let Some(input) = self.mapper.map(&event.input) {
+                inputs.push(input);
+            }
+            self.next_event += 1;
+        }
+
+        world.step(TICK_DT, &inputs);
+        self.tick += 1;
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use piston::input::{Button, Key};
+
+    fn press(key: Key, pressed: bool) -> RawInput {
+        RawInput::Button { button: Button::Keyboard(key), pressed }
+    }
+
+    // a file of its own in the temp dir, so tests running side by side don't clash.
+    fn temp_file(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("gun-score-{}-{}.json", name, std::process::id()))
+    }
+
+    #[test]
+    fn a_saved_replay_plays_out_like_the_live_run() {
+        let config = GameConfig::default();
+        let level = Level::open_field(&config);
+        let mut replay = Replay::new(7, config.clone(), level.clone());
+        let mut world = GameWorld::new(config.clone(), level, 7);
+        let mut mapper = InputMapper::new(&config.controls);
+
+        // walk down and right while firing, the way the front-end records a run.
+        let script = [(0, press(Key::S, true)), (0, press(Key::Space, true)), (30, press(Key::Space, false)),
+            (60, press(Key::D, true)), (90, RawInput::Cursor([300.0, 200.0])), (120, press(Key::Space, true))];
+        for tick in 0..600 {
+            let mut inputs = Vec::new();
+            for (_, input) in script.iter().filter(|(at, _)| *at == tick) {
+                replay.record_input(*input);
+                inputs.extend(mapper.map(input));
+            }
+            world.step(TICK_DT, &inputs);
+            replay.record_tick();
+        }
+
+        let path = temp_file("round-trip");
+        replay.save(&path).unwrap();
+        let loaded = Replay::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let played = loaded.simulate();
+
+        assert_eq!(loaded, replay);
+        assert_eq!(played.player().score, world.player().score);
+        assert_eq!(played.status, world.status);
+        assert_eq!(played.player_pos(), world.player_pos());
+        assert_eq!(played.player_health(), world.player_health());
+    }
+
+    #[test]
+    fn another_version_is_refused() {
+        let config = GameConfig::default();
+        let level = Level::open_field(&config);
+        let mut replay = Replay::new(1, config, level);
+        replay.version = REPLAY_VERSION - 1;
+
+        let path = temp_file("old-version");
+        replay.save(&path).unwrap();
+        let result = Replay::load(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(matches!(result, Err(ReplayError::Version(version)) if version == REPLAY_VERSION - 1));
+    }
+
+    #[test]
+    fn playback_feeds_inputs_on_their_tick() {
+        let config = GameConfig::default();
+        let level = Level::open_field(&config);
+        let mut replay = Replay::new(1, config, level);
+        for _ in 0..5 {
+            replay.record_tick();
+        }
+        replay.record_input(press(Key::Space, true));
+        replay.record_tick();
+
+        let mut world = replay.new_world();
+        let mut playback = Playback::new(replay);
+        for _ in 0..5 {
+            assert!(playback.step(&mut world));
+        }
+        assert!(world.entities.bullets.is_empty());
+
+        assert!(playback.step(&mut world));
+        assert_eq!(world.entities.bullets.len(), 1);
+        assert!(playback.is_finished());
+        assert!(!playback.step(&mut world));
+    }
+}
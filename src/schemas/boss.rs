@@ -0,0 +1,85 @@
+use graphics::{color};
+use rand::Rng;
+
+use crate::ai::{Brain};
+use crate::collider::{Collider};
+use crate::config::{BossAttack, BossConfig, BossPhase};
+use crate::ecs::{Entities, Entity};
+use crate::ecs::components::{Confined, Contact, Damage, Health, Renderable, Team, Transform, Velocity};
+use crate::geom::{Position, Vec2};
+use crate::util::{get_random_in, new_rng, GameRng};
+
+// the end of the wave set: a lot of health and attacks that get nastier as it loses it.
+pub struct Boss {
+    pub name: String,
+    pub phases: Vec<BossPhase>,
+    pub phase: usize, // index into `phases`
+    pub cooldowns: Vec<f64>, // secs until each attack of the phase fires again
+    pub spin: f64, // radians, where the next spiral volley starts
+    pub difficulty: f64, // of the last wave, passed on to its minions
+    pub brain: Brain, // seeded from the world's rng
+}
+
+impl Boss {
+    // `difficulty` multiplies its health and damage.
+    pub fn spawn(entities: &mut Entities, pos: Position, config: &BossConfig, difficulty: f64, rng: &mut GameRng) -> Entity {
+        let size = config.size;
+        let entity = entities.spawn();
+        entities.transforms.insert(entity, Transform::new(pos));
+        entities.velocities.insert(entity, Velocity::default());
+        entities.confined.insert(entity, Confined { margin: size / 2.0 });
+        entities.colliders.insert(entity, Collider::Aabb { half: Vec2::new(size / 2.0, size / 2.0) });
+        entities.healths.insert(entity, Health::new(config.health * difficulty));
+        entities.damages.insert(entity, Damage(get_random_in(rng, config.damage) * difficulty as f32));
+        entities.contacts.insert(entity, Contact(config.contact * difficulty));
+        entities.teams.insert(entity, Team::Monster);
+        entities.renderables.insert(entity, Renderable::Square { size, color: color::hex(&config.color) });
+
+        let mut boss = Boss {
+            name: config.name.clone(),
+            phases: config.phases.clone(),
+            phase: 0,
+            cooldowns: Vec::new(),
+            spin: 0.0,
+            difficulty,
+            brain: Brain::new(config.ai, pos, new_rng(rng.gen())),
+        };
+        boss.enter(0);
+        entities.bosses.insert(entity, boss);
+
+        entity
+    }
+
+    // the phase for a `health` share (0..1): the last one it has dropped to.
+    pub fn phase_at(phases: &[BossPhase], health: f64) -> usize {
+        phases.iter().rposition(|phase| health <= phase.from).unwrap_or(0)
+    }
+
+    // start the phase at `index`, every attack gets its full cooldown first so the change can be seen coming.
+    pub fn enter(&mut self, index: usize) {
+        self.phase = index;
+        self.cooldowns = self.attacks().iter().map(|attack| attack.cooldown()).collect();
+    }
+
+    pub fn attacks(&self) -> &[BossAttack] {
+        self.phases.get(self.phase).map_or(&[], |phase| &phase.attacks)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn phases_change_at_their_thresholds() {
+        let phases = BossConfig::default().phases;
+        assert_eq!(phases.iter().map(|phase| phase.from).collect::<Vec<f64>>(), vec![1.0, 0.66, 0.33]);
+
+        assert_eq!(Boss::phase_at(&phases, 1.0), 0);
+        assert_eq!(Boss::phase_at(&phases, 0.7), 0);
+        assert_eq!(Boss::phase_at(&phases, 0.66), 1);
+        assert_eq!(Boss::phase_at(&phases, 0.4), 1);
+        assert_eq!(Boss::phase_at(&phases, 0.1), 2);
+        assert_eq!(Boss::phase_at(&phases, -5.0), 2);
+    }
+}
@@ -1,87 +1,30 @@
 //source: https://github.com/a5huynh/defender-game/blob/master/src/models/bullet.rs
 
-use graphics::{Context, Transformed, ellipse};
-use opengl_graphics::{GlGraphics};
-
-use piston::window::Size;
-
-use crate::geom::{Position, Direction};
-use crate::util::{get_random_number};
+use crate::collider::{Collider};
 use crate::colors::{Colors};
+use crate::ecs::{Entities, Entity};
+use crate::ecs::components::{Damage, Lifetime, Renderable, Team, Transform, Velocity};
+use crate::geom::{Position, Vec2};
 
-use super::{GameObject};
+const BULLET_SIZE: f64 = 10.0; // diameter
 
-const BULLET_SPEED: f64 = 2.0;
-const BULLET_SIZE: f64 = 20.0;
-const BULLET_LIFETIME: f64 = 2.0;
-
-#[derive(PartialEq)]
-pub enum Attacker {
-    Monster,
-    Player,
-}
-
-pub struct Bullet {
-    pub pos: Position,
-    pub direction: Direction,
-    pub size: f64,
-    pub ttl: f64,
-    pub damage_count: f32,
-    pub attacker: Attacker,
-    pub source_direction: Direction,
-}
+// flies straight until it hits something or its lifetime runs out.
+pub struct Bullet;
 
 impl Bullet {
-    pub fn new(x: f64, y: f64, direction: Direction, attacker: Attacker) -> Self {
-        Bullet {
-            pos: Position::new(x,y),
-            direction,
-            size: BULLET_SIZE,
-            ttl: BULLET_LIFETIME,
-            damage_count: get_random_number::<f32>(5.0, 20.0),
-            attacker,
-            source_direction: match direction {
-				Direction::EAST => Direction::WEST,
-				Direction::WEST => Direction::EAST,
-				Direction::NORTH => Direction::SOUTH,
-				Direction::SOUTH => Direction::NORTH,
-			},
-        }
-    }
-
-    pub fn radius(&self) -> f64 {
-        self.size / 2.0
-    }
-}
-
-impl GameObject for Bullet {
-    fn position(&self) -> &Position {
-        &self.pos
-    }
-
-    fn radius(&self) -> f64 {
-        BULLET_SIZE
-    }
-
-    fn render(&self, ctx: &Context, gl: &mut GlGraphics) {
-        let colors = Colors::init();
-        let radius = self.radius();
-        let transformer = ctx.transform
-            .trans(self.pos.x, self.pos.y);
-        ellipse(colors.black, [0.0, 0.0, radius, radius], transformer, gl);
-        //clear
-        drop(colors);
-        drop(radius);
-        drop(transformer);
-    }
-
-    fn update(&mut self, dt: f64, _: Size) {
-        self.ttl -= dt;
-        match self.direction {
-            Direction::EAST => self.pos.x += BULLET_SPEED,
-            Direction::NORTH => self.pos.y -= BULLET_SPEED,
-            Direction::WEST => self.pos.x -= BULLET_SPEED,
-            Direction::SOUTH => self.pos.y += BULLET_SPEED,
-        }
+    // `angle` is in radians, 0 is east and y points down, `speed` is px/sec and `lifetime` secs.
+    pub fn spawn(entities: &mut Entities, pos: Position, angle: f64, speed: f64, lifetime: f64, damage: f32, team: Team) -> Entity {
+        let radius = BULLET_SIZE / 2.0;
+        let entity = entities.spawn();
+        entities.transforms.insert(entity, Transform::new(pos));
+        entities.velocities.insert(entity, Velocity(Vec2::from_angle(angle, speed)));
+        entities.colliders.insert(entity, Collider::Circle { radius });
+        entities.lifetimes.insert(entity, Lifetime(lifetime));
+        entities.damages.insert(entity, Damage(damage));
+        entities.teams.insert(entity, team);
+        entities.renderables.insert(entity, Renderable::Circle { radius, color: Colors::init().black });
+        entities.bullets.insert(entity, Bullet);
+
+        entity
     }
 }
@@ -1,120 +1,131 @@
 //source: https://github.com/a5huynh/defender-game/blob/master/src/models/enemy.rs
 
-use graphics::{Context, rectangle, Transformed};
-use opengl_graphics::{GlGraphics};
-
-use crate::util::{get_random_number};
-use crate::geom::{Position, restrict_to_bounds};
-use crate::colors::Colors;
-
-use piston::window::Size;
+use std::collections::BTreeMap;
+
+use graphics::{color};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::ai::{Brain};
+use crate::collider::{Collider};
+use crate::config::{EnemyConfig};
+use crate::ecs::{Entities, Entity};
+use crate::ecs::components::{Confined, Contact, Damage, Explosive, Health, Renderable, Shield, Shooter, Splitter, Team, Transform, Velocity};
+use crate::util::{get_random_in, get_random_number, get_random_weighted, new_rng, GameRng};
+use crate::geom::{Position, Vec2};
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash, Copy, Clone, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum EnemyType {
+    Kind, // wanders about, only dangerous to touch
+    Fighter, // shoots at the player
+    Charger, // small and fast, runs straight at the player
+    Bruiser, // slow, big and hard to kill
+    Splitter, // breaks up into smaller enemies when killed
+    Exploder, // blows up next to the player
+    Shielded, // can only be hurt from behind
+}
 
-use super::GameObject;
+impl EnemyType {
+    pub const ALL: [EnemyType; 7] = [
+        EnemyType::Kind,
+        EnemyType::Fighter,
+        EnemyType::Charger,
+        EnemyType::Bruiser,
+        EnemyType::Splitter,
+        EnemyType::Exploder,
+        EnemyType::Shielded,
+    ];
+
+    // the name used in the config, e.g. `[enemy.charger]`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            EnemyType::Kind => "kind",
+            EnemyType::Fighter => "fighter",
+            EnemyType::Charger => "charger",
+            EnemyType::Bruiser => "bruiser",
+            EnemyType::Splitter => "splitter",
+            EnemyType::Exploder => "exploder",
+            EnemyType::Shielded => "shielded",
+        }
+    }
 
-#[derive(PartialEq, Copy, Clone)]
-pub enum EnemyType {
-    Kind,
-    Fighter,
+    // one of the types of a wave's `mix`, more likely the higher its weight.
+    pub fn pick(mix: &BTreeMap<EnemyType, f64>, rng: &mut GameRng) -> EnemyType {
+        get_random_weighted(rng, mix).unwrap_or(EnemyType::Kind)
+    }
 }
 
-#[derive(PartialEq)]
 pub struct Enemy {
-    pub health: f64,
-    pub pos: Position,
-    pub size: f64, // enemy's body size
-    move_ttl: f64,
     pub enemy_type: EnemyType,
-    pub damage_count: f32,
+    pub difficulty: f64, // of the wave it came with, passed on to what it splits into
+    pub brain: Brain, // seeded from the world's rng
 }
 
-const MOVE_RADIUS: f64 = 10.0;
-const MOVE_TTL: f64 = 0.1; // 0.1 secs / 100 ms
-const ENEMY_RADIUS: f64 = 10.0;
-
 impl Enemy {
-    pub fn new(x: f64, y: f64) -> Self {
-        let enemy_type: EnemyType = match get_random_number::<u8>(0, 2) {
-            0 => EnemyType::Kind,
-            1 => EnemyType::Fighter,
-            _ => EnemyType::Kind,
-        };
-
-        Enemy {
-            health: if enemy_type == EnemyType::Kind {
-                get_random_number::<f64>(10.0, 100.0)
-            } else {
-                get_random_number::<f64>(20.0, 1000.0)
-            },
-            move_ttl: MOVE_TTL,
-            pos: Position::new(x,y),
-            size: ENEMY_RADIUS * 3.0,
+    // `difficulty` multiplies the health and damage picked from the type's stats.
+    pub fn spawn(entities: &mut Entities, pos: Position, enemy_type: EnemyType, difficulty: f64, config: &EnemyConfig, rng: &mut GameRng) -> Entity {
+        let stats = config.stats(enemy_type);
+        let health = get_random_in(rng, stats.health) * difficulty;
+        let damage = get_random_in(rng, stats.damage) * difficulty as f32;
+        let size = stats.size;
+
+        let entity = entities.spawn();
+        entities.transforms.insert(entity, Transform::new(pos));
+        entities.velocities.insert(entity, Velocity::default());
+        entities.confined.insert(entity, Confined { margin: size / 2.0 });
+        // enemies are drawn as squares, so they collide as squares.
+        entities.colliders.insert(entity, Collider::Aabb { half: Vec2::new(size / 2.0, size / 2.0) });
+        entities.healths.insert(entity, Health::new(health));
+        entities.damages.insert(entity, Damage(damage));
+        entities.contacts.insert(entity, Contact(stats.contact * difficulty));
+        entities.teams.insert(entity, Team::Monster);
+        entities.renderables.insert(entity, Renderable::Square { size, color: color::hex(&stats.color) });
+        entities.enemies.insert(entity, Enemy {
             enemy_type,
-            damage_count: if enemy_type == EnemyType::Fighter {
-                drop(enemy_type);
-                get_random_number::<f32>(10.0, 60.0)
-            } else {
-                drop(enemy_type);
-                0.0 // if the enemy_type is EnemyType::Kind
-            }
+            difficulty,
+            brain: Brain::new(stats.ai, pos, new_rng(rng.gen())),
+        });
+
+        // shooters fire on their own, a bit apart from each other so a wave doesn't fire all at once.
+        if let Some(gun) = stats.gun {
+            entities.shooters.insert(entity, Shooter {
+                cooldown: gun.cooldown,
+                ready_in: get_random_number::<f64>(rng, 0.5, 1.0) * gun.cooldown,
+                range: gun.range,
+                lead: gun.lead,
+                spread: gun.spread,
+            });
+        }
+        if let Some(split) = stats.split {
+            entities.splitters.insert(entity, Splitter { count: split.count, into: split.into });
+        }
+        if let Some(explosion) = stats.explosion {
+            entities.explosives.insert(entity, Explosive { radius: explosion.radius });
+        }
+        if stats.shield > 0.0 {
+            entities.shields.insert(entity, Shield { arc: stats.shield, turn: stats.shield_turn });
         }
-    }
 
-    // generate random monster/enemy.
-    pub fn new_rand(max_x: f64, max_y: f64) -> Enemy {
-        let randx = get_random_number::<f64>(0.0, max_x);
-        let randy = get_random_number::<f64>(0.0, max_y);
-        Enemy::new(randx, randy)
+        entity
     }
 }
 
-impl GameObject for Enemy {
-    fn position(&self) -> &Position {
-        &self.pos
-    }
+pub type Monster = Enemy;
 
-    fn radius(&self) -> f64 {
-        self.size / 2.0
-    }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::{new_rng};
 
-    fn render(&self, ctx: &Context, gl: &mut GlGraphics) {
-        let colors = Colors::init();
-        // TODO: change the enemy's body to image.
-        let square = rectangle::square(0.0, 0.0, self.size);
-        let radius = self.radius();
-        let transform = ctx.transform.trans(self.pos.x, self.pos.y)
-            .trans(-radius, -radius);
-        let color = match self.enemy_type {
-            EnemyType::Kind => colors.green,
-            EnemyType::Fighter => colors.red,
-        };
-
-        rectangle(color, square, transform, gl);
-        //get out
-        drop(color);
-        drop(square);
-        drop(colors);
-        drop(transform);
-    }
+    #[test]
+    fn picks_by_weight() {
+        let mut rng = new_rng(3);
+        let mix = BTreeMap::from([(EnemyType::Kind, 3.0), (EnemyType::Bruiser, 1.0), (EnemyType::Charger, 0.0)]);
+        let picks: Vec<EnemyType> = (0..4000).map(|_| EnemyType::pick(&mix, &mut rng)).collect();
+        let bruisers = picks.iter().filter(|enemy_type| **enemy_type == EnemyType::Bruiser).count();
 
-    fn update(&mut self, dt: f64, size: Size) {
-        // Only move every <MOVE_TTL> seconds
-        self.move_ttl -= dt;
-        if self.move_ttl <= 0.0 {
-            // Randomly move in a random direction.
-            let radius = self.radius();
-            self.pos.x += get_random_number::<f64>(0.0, MOVE_RADIUS * 2.0)
-                - MOVE_RADIUS;
-            self.pos.y += get_random_number::<f64>(0.0, MOVE_RADIUS * 2.0)
-                - MOVE_RADIUS;
-
-            restrict_to_bounds(
-                &mut self.pos,
-                [radius, radius, f64::from(size.width), f64::from(size.height)]
-                );
-                // Don't move outside the bounds of the window.
-                self.move_ttl = MOVE_TTL;
-        }
+        assert!(!picks.contains(&EnemyType::Charger));
+        assert!((800..1200).contains(&bruisers), "{} bruisers", bruisers);
     }
 }
-
-pub type Monster = Enemy;
@@ -0,0 +1,21 @@
+use crate::ecs::{Entities, Entity};
+use crate::ecs::components::{Lifetime, Renderable, Transform};
+use crate::geom::{Position};
+
+// how long the blast stays on screen, secs.
+const EXPLOSION_TIME: f64 = 0.25;
+
+// the flash of an exploder blowing up, only there to be seen: the damage is dealt at once.
+pub struct Explosion;
+
+impl Explosion {
+    pub fn spawn(entities: &mut Entities, pos: Position, radius: f64) -> Entity {
+        let entity = entities.spawn();
+        entities.transforms.insert(entity, Transform::new(pos));
+        entities.lifetimes.insert(entity, Lifetime(EXPLOSION_TIME));
+        entities.renderables.insert(entity, Renderable::Circle { radius, color: [0.95, 0.6, 0.1, 0.5] });
+        entities.explosions.insert(entity, Explosion);
+
+        entity
+    }
+}
@@ -1,26 +1,10 @@
-use crate::geom::Position;
-use graphics::{Context};
-use opengl_graphics::GlGraphics;
-use piston::window::Size;
+// the kinds of entities in the game: each one is a component with the state only
+// that kind needs, plus a `spawn` that puts it together with the shared components.
 
 pub mod player;
 pub mod enemy;
+pub mod boss;
 pub mod bullet;
-
-pub trait GameObject {
-    fn tabrakan(&self, object: &dyn GameObject) -> bool {
-        let x = self.position().x - object.position().x;
-        let y = self.position().y - object.position().y;
-        let sums = x.powf(2.0) + y.powf(2.0);
-
-        let radius_start = self.radius() - object.radius();
-        let radius_end = self.radius() + object.radius();
-        radius_start.powf(2.0) <= sums && sums <= radius_end.powf(2.0)
-    }
-
-    fn position(&self) -> &Position;
-    fn radius(&self) -> f64;
-
-    fn render(&self, ctx: &Context, gl: &mut GlGraphics);
-    fn update(&mut self, _: f64, _: Size) {}
-}
+pub mod obstacle;
+pub mod explosion;
+pub mod pickup;
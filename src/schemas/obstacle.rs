@@ -0,0 +1,20 @@
+use crate::collider::{Collider};
+use crate::colors::{Colors};
+use crate::ecs::{Entities, Entity};
+use crate::ecs::components::{Renderable, Transform};
+use crate::geom::{Rect};
+
+// a wall of the level: it never moves, stops whatever runs into it and eats bullets.
+pub struct Obstacle;
+
+impl Obstacle {
+    pub fn spawn(entities: &mut Entities, rect: &Rect) -> Entity {
+        let entity = entities.spawn();
+        entities.transforms.insert(entity, Transform::new(rect.center()));
+        entities.colliders.insert(entity, Collider::Aabb { half: rect.half() });
+        entities.renderables.insert(entity, Renderable::Rect { width: rect.width, height: rect.height, color: Colors::init().blue });
+        entities.obstacles.insert(entity, Obstacle);
+
+        entity
+    }
+}
@@ -0,0 +1,81 @@
+use serde::{Deserialize, Serialize};
+
+use crate::collider::{Collider};
+use crate::colors::{ColorComps};
+use crate::ecs::{Entities, Entity};
+use crate::ecs::components::{Lifetime, Renderable, Transform};
+use crate::geom::{Position};
+
+const PICKUP_SIZE: f64 = 16.0; // diameter
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash, Copy, Clone, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum PickupKind {
+    Health, // heals the player
+    Ammo, // refills the reserve of every weapon
+    Life, // one more life
+    RapidFire, // fires faster for a while
+    Shield, // can't be hurt for a while
+    Damage, // bullets hurt more for a while
+    Speed, // moves faster for a while
+}
+
+impl PickupKind {
+    // the name used in the config, e.g. `rapid_fire = 1.0`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            PickupKind::Health => "health",
+            PickupKind::Ammo => "ammo",
+            PickupKind::Life => "life",
+            PickupKind::RapidFire => "rapid_fire",
+            PickupKind::Shield => "shield",
+            PickupKind::Damage => "damage",
+            PickupKind::Speed => "speed",
+        }
+    }
+
+    // shown in the HUD while the buff lasts.
+    pub fn title(&self) -> &'static str {
+        match self {
+            PickupKind::Health => "Health",
+            PickupKind::Ammo => "Ammo",
+            PickupKind::Life => "Extra life",
+            PickupKind::RapidFire => "Rapid fire",
+            PickupKind::Shield => "Shield",
+            PickupKind::Damage => "Damage boost",
+            PickupKind::Speed => "Speed boost",
+        }
+    }
+
+    pub fn color(&self) -> ColorComps {
+        match self {
+            PickupKind::Health => [0.2, 0.8, 0.3, 1.0],
+            PickupKind::Ammo => [0.85, 0.7, 0.2, 1.0],
+            PickupKind::Life => [0.95, 0.3, 0.6, 1.0],
+            PickupKind::RapidFire => [0.95, 0.45, 0.1, 1.0],
+            PickupKind::Shield => [0.3, 0.6, 0.95, 1.0],
+            PickupKind::Damage => [0.75, 0.1, 0.1, 1.0],
+            PickupKind::Speed => [0.6, 0.3, 0.9, 1.0],
+        }
+    }
+}
+
+// left behind by a killed monster, the player collects it by walking over it.
+pub struct Pickup {
+    pub kind: PickupKind,
+}
+
+impl Pickup {
+    // lies around for `lifetime` secs.
+    pub fn spawn(entities: &mut Entities, pos: Position, kind: PickupKind, lifetime: f64) -> Entity {
+        let radius = PICKUP_SIZE / 2.0;
+        let entity = entities.spawn();
+        entities.transforms.insert(entity, Transform::new(pos));
+        entities.colliders.insert(entity, Collider::Circle { radius });
+        entities.lifetimes.insert(entity, Lifetime(lifetime));
+        entities.renderables.insert(entity, Renderable::Circle { radius, color: kind.color() });
+        entities.pickups.insert(entity, Pickup { kind });
+
+        entity
+    }
+}
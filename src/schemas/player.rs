@@ -1,117 +1,269 @@
 // source: https://github.com/a5huynh/defender-game/blob/master/src/models/player.rs
 
-use opengl_graphics::{GlGraphics};
-use graphics::{Context, Transformed};
-use piston::window::Size;
-use crate::geom::{Position, Direction, restrict_to_bounds};
-use super::GameObject;
-use crate::textures::{IconStruct, get_icon};
+use std::collections::BTreeMap;
 
-const PLAYER_DRIFT: f64 = 0.2;
-const PLAYER_SPEED: f64 = 5.0;
-const PLAYER_SIZE: f64 = 5.0;
+use crate::collider::{Collider};
+use crate::config::{PlayerConfig, WeaponConfig};
+use crate::ecs::{Entities, Entity};
+use crate::ecs::components::{Confined, Health, Renderable, Team, Transform, Velocity};
+use crate::geom::{Position, Vec2, Direction};
+use crate::schemas::pickup::{PickupKind};
+use crate::util::{GameRng};
+use crate::weapon::{Weapon};
+
+const PLAYER_SIZE: f64 = 50.0; // the gun icon is drawn this big
+
+// a pickup's effect that wears off after a while.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub struct Buff {
+    pub left: f64, // secs
+    pub boost: f64, // multiplies the stat it is about
+}
 
 pub struct Player {
     pub name: String,
-    pub pos: Position,
-    move_offset: Position,
-    pub drift_ttl: f64,
-    pub direction: Direction,
-    pub size: f64,
+    held: [bool; 4], // movement keys held down, indexed by `Direction`
+    stick: Vec2, // analog movement, each axis in -1..1
+    pub facing: f64, // radians, where the player last moved
+    pub aim: f64, // radians, where the gun points
+    pub aim_target: Option<Position>, // the spot the mouse aims at, the gun keeps pointing there while moving
+    pub speed: f64, // max speed, px/sec
+    pub acceleration: f64, // px/sec^2
+    pub friction: f64, // px/sec^2, slows the player down once nothing is held
     pub life: u8,
-    pub icon: IconStruct,
-    pub amunition: u64,
-    pub shooting: bool,
+    pub auto_reload: bool, // reload once the magazine runs empty
+    pub weapons: Vec<Weapon>,
+    pub weapon: usize, // index of the one in hand
+    pub trigger: bool, // the fire button is held down
+    pub pulled: bool, // the fire button was pressed since the last shot
+    pub buffs: BTreeMap<PickupKind, Buff>, // the ones still running
     pub score: f64,
-    pub health: f64,
 }
 
 impl Player {
-    pub fn new(name: &String, x: &f64, y: &f64) -> Self {
-        let position = Position::new(*x, *y);
-        Player {
+    pub fn spawn(entities: &mut Entities, name: &str, pos: Position, config: &PlayerConfig, weapons: &[WeaponConfig]) -> Entity {
+        let radius = PLAYER_SIZE / 2.0;
+        let entity = entities.spawn();
+        entities.transforms.insert(entity, Transform::new(pos));
+        entities.velocities.insert(entity, Velocity::default());
+        entities.confined.insert(entity, Confined { margin: radius });
+        // the gun, turned the way it points.
+        entities.colliders.insert(entity, Collider::Obb { half: Vec2::new(radius, radius), angle: 0.0 });
+        entities.healths.insert(entity, Health::new(config.health));
+        entities.teams.insert(entity, Team::Player);
+        entities.renderables.insert(entity, Renderable::Icon { size: PLAYER_SIZE });
+        entities.players.insert(entity, Player {
             name: name.to_string(),
-            pos: position,
-            move_offset: Position::new(0.0, 0.0),
-            drift_ttl: 0.0,
-            direction: Direction::EAST,
-            size: PLAYER_SIZE,
-            life: 5,
-            icon: get_icon(&position),
-            amunition: 100,
-            shooting: false,
+            held: [false; 4],
+            stick: Vec2::zero(),
+            facing: Direction::EAST.angle(),
+            aim: Direction::EAST.angle(),
+            aim_target: None,
+            speed: config.speed,
+            acceleration: config.acceleration,
+            friction: config.friction,
+            life: config.life,
+            auto_reload: config.auto_reload,
+            weapons: weapons.iter().map(Weapon::new).collect(),
+            weapon: 0,
+            trigger: false,
+            pulled: false,
+            buffs: BTreeMap::new(),
             score: 0.0,
-            health: 1000.0,
+        });
+
+        entity
+    }
+
+    pub fn weapon(&self) -> &Weapon {
+        &self.weapons[self.weapon]
+    }
+
+    pub fn weapon_mut(&mut self) -> &mut Weapon {
+        &mut self.weapons[self.weapon]
+    }
+
+    // take the weapon in `slot` in hand, if there is one. Putting a weapon away breaks off its reload.
+    pub fn select_weapon(&mut self, slot: usize) {
+        if slot < self.weapons.len() && slot != self.weapon {
+            self.weapon_mut().cancel_reload();
+            self.weapon = slot;
         }
     }
 
-    pub fn start_move(&mut self, dir: Direction) {
-        self.direction = dir;
-        match dir {
-            Direction::WEST => self.move_offset.x = -PLAYER_SPEED,
-            Direction::NORTH => self.move_offset.y = -PLAYER_SPEED,
-            Direction::EAST => self.move_offset.x = PLAYER_SPEED,
-            Direction::SOUTH => self.move_offset.y = PLAYER_SPEED,
+    // the next (`step` 1) or previous (`step` -1) weapon, going round.
+    pub fn cycle_weapon(&mut self, step: isize) {
+        let count = self.weapons.len() as isize;
+        self.select_weapon((self.weapon as isize + step).rem_euclid(count) as usize);
+    }
+
+    pub fn press_trigger(&mut self) {
+        self.trigger = true;
+        self.pulled = true;
+    }
+
+    pub fn release_trigger(&mut self) {
+        self.trigger = false;
+    }
+
+    // a semi-automatic weapon fires once per press, a full-automatic one as long as the trigger is held.
+    pub fn wants_to_fire(&self) -> bool {
+        self.pulled || (self.trigger && self.weapon().config.automatic)
+    }
+
+    // pull the trigger: the angles of the bullets that come out, none with an empty magazine.
+    // If the player wants that, the shot that empties the magazine starts a reload, as does firing an empty one.
+    pub fn fire(&mut self, rng: &mut GameRng) -> Vec<f64> {
+        self.pulled = false;
+        let (aim, auto_reload) = (self.aim, self.auto_reload);
+        let weapon = self.weapon_mut();
+        let angles = weapon.fire(aim, rng);
+        if weapon.is_empty() && auto_reload {
+            weapon.reload();
         }
+        angles
     }
 
-    pub fn stop_move(&mut self, dir: Direction) {
-        self.drift_ttl = PLAYER_DRIFT;
-        match dir {
-            Direction::WEST => self.move_offset.x = 0.0,
-            Direction::NORTH => self.move_offset.y = 0.0,
-            Direction::EAST => self.move_offset.x = 0.0,
-            Direction::SOUTH => self.move_offset.y = 0.0,
+    // start the buff of `kind`, or make a running one last at least `secs` again.
+    pub fn add_buff(&mut self, kind: PickupKind, secs: f64, boost: f64) {
+        let buff = self.buffs.entry(kind).or_insert(Buff { left: 0.0, boost });
+        buff.left = buff.left.max(secs);
+        buff.boost = boost;
+    }
+
+    // how much the buff of `kind` multiplies its stat by right now, 1 without it.
+    pub fn boost(&self, kind: PickupKind) -> f64 {
+        self.buffs.get(&kind).map_or(1.0, |buff| buff.boost)
+    }
+
+    // the shield pickup keeps every kind of damage off.
+    pub fn is_shielded(&self) -> bool {
+        self.buffs.contains_key(&PickupKind::Shield)
+    }
+
+    // count the buffs down and drop the ones that wore off.
+    pub fn update_buffs(&mut self, dt: f64) {
+        for buff in self.buffs.values_mut() {
+            buff.left -= dt;
         }
+        self.buffs.retain(|_, buff| buff.left > 0.0);
     }
-}
 
-impl GameObject for Player {
-    fn position(&self) -> &Position { &self.pos }
-    fn radius(&self) -> f64 { self.size / 2.0 }
+    // point the gun from `pos` at `target`, e.g. the mouse cursor, and keep it there from now on.
+    pub fn aim_at(&mut self, pos: Position, target: Position) {
+        self.aim_target = Some(target);
+        self.aim = (target - pos).angle();
+    }
 
-    fn render(&self, ctx: &Context, gl: &mut GlGraphics) {
-        let direction = match self.direction {
-            Direction::WEST => 180.0,
-            Direction::NORTH => 270.0,
-            Direction::EAST => 0.0,
-            Direction::SOUTH => 90.0,
-        };
+    pub fn start_move(&mut self, dir: Direction) {
+        self.held[dir as usize] = true;
+    }
 
-        let radius = self.radius();
+    pub fn stop_move(&mut self, dir: Direction) {
+        self.held[dir as usize] = false;
+    }
 
-        let mut transform = ctx.transform
-            .trans(self.pos.x, self.pos.y)
-            .rot_deg(direction)
-            .trans(-radius, -radius);
+    // move with an analog stick, `x` and `y` in -1..1.
+    pub fn analog_move(&mut self, x: f64, y: f64) {
+        self.stick = Vec2::new(x, y);
+    }
 
-        // flip the gun
-        if self.direction == Direction::WEST {
-            transform = transform.flip_v();
+    // where the player wants to go, at most 1 long.
+    // The stick wins while it is pushed, otherwise the held keys are combined,
+    // so two keys make a diagonal and opposite keys cancel out.
+    fn wish(&self) -> Vec2 {
+        if !self.stick.is_zero() {
+            return self.stick;
+        }
+
+        let mut wish = Vec2::zero();
+        for dir in [Direction::WEST, Direction::NORTH, Direction::EAST, Direction::SOUTH] {
+            if self.held[dir as usize] {
+                wish += dir.offset();
+            }
         }
 
-        self.icon.img.draw(&self.icon.texture, &ctx.draw_state, transform, gl);
-        drop(radius);
-        drop(transform);
+        wish.normalize()
     }
 
-    fn update(&mut self, dt: f64, size: Size) {
-        let radius = self.radius();
-        self.pos.x += self.move_offset.x;
-        self.pos.y += self.move_offset.y;
+    // speed up towards where the player wants to go, or slow down to a stop.
+    // The gun follows the aimed at spot from `pos`, or else where the player moves.
+    pub fn steer(&mut self, pos: Position, velocity: &mut Vec2, dt: f64) {
+        let wish = self.wish();
+        let moving = !wish.is_zero();
 
-        if self.drift_ttl > 0.0 {
-            self.drift_ttl -= dt;
-            let drift_speed = PLAYER_SPEED / 2.0;
-            match self.direction {
-                Direction::NORTH => self.pos.y -= drift_speed,
-                Direction::EAST => self.pos.x += drift_speed,
-                Direction::SOUTH => self.pos.y += drift_speed,
-                Direction::WEST => self.pos.x -= drift_speed,
-            }
+        if moving {
+            self.facing = wish.angle();
+        }
+        match self.aim_target {
+            Some(target) if target != pos => self.aim = (target - pos).angle(),
+            Some(_) => {},
+            None if moving => self.aim = self.facing,
+            None => {},
+        }
+
+        let rate = if moving { self.acceleration } else { self.friction };
+        *velocity = velocity.move_towards(&(wish * self.speed * self.boost(PickupKind::Speed)), rate * dt);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn player() -> Player {
+        let mut entities = Entities::new();
+        let entity = Player::spawn(&mut entities, "Test", Position::zero(), &PlayerConfig::default(), &WeaponConfig::defaults());
+        entities.players.remove(entity).unwrap()
+    }
+
+    // steer for `secs`, starting from `velocity`.
+    fn steer(player: &mut Player, mut velocity: Vec2, secs: f64) -> Vec2 {
+        for _ in 0..(secs * 120.0) as usize {
+            player.steer(Position::zero(), &mut velocity, 1.0 / 120.0);
         }
+        velocity
+    }
+
+    #[test]
+    fn diagonals_are_not_faster() {
+        let mut player = player();
+        player.start_move(Direction::EAST);
+        player.start_move(Direction::SOUTH);
+        let velocity = steer(&mut player, Vec2::zero(), 1.0);
+
+        assert!(velocity.length() <= player.speed + 1e-9);
+        assert!((velocity.x - velocity.y).abs() < 1e-9);
+    }
+
+    #[test]
+    fn opposite_keys_cancel_out() {
+        let mut player = player();
+        player.start_move(Direction::WEST);
+        player.start_move(Direction::EAST);
+
+        assert_eq!(steer(&mut player, Vec2::zero(), 0.5), Vec2::zero());
+    }
+
+    #[test]
+    fn releasing_one_key_of_a_diagonal_keeps_the_other() {
+        let mut player = player();
+        player.start_move(Direction::NORTH);
+        player.start_move(Direction::EAST);
+        let velocity = steer(&mut player, Vec2::zero(), 1.0);
+
+        player.stop_move(Direction::NORTH);
+        let velocity = steer(&mut player, velocity, 1.0);
+        assert!((velocity.x - player.speed).abs() < 1e-9);
+        assert!(velocity.y.abs() < 1e-9);
+    }
+
+    #[test]
+    fn friction_stops_the_player() {
+        let mut player = player();
+        let (speed, stopping) = (player.speed, player.speed / player.friction);
+        let velocity = steer(&mut player, Vec2::new(speed, 0.0), stopping + 0.1);
 
-        restrict_to_bounds(&mut self.pos, [radius, radius, f64::from(size.width), f64::from(size.height)]);
-        drop(radius);
+        assert_eq!(velocity, Vec2::zero());
     }
 }
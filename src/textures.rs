@@ -21,15 +21,15 @@ pub fn get_icon(pos: &Position) -> IconStruct {
 
     IconStruct {
         img: image,
-        texture: texture,
+        texture,
     }
 }
 
-pub fn load_font(font_bytes: &'static [u8]) -> Font<'_> {
+pub fn load_font(font_bytes: &'static [u8]) -> Font<'static> {
     Font::try_from_bytes(font_bytes).expect("Unable to get font from u8-bytes")
 }
 
-pub fn load_cache(font_bytes: &'static [u8]) -> GlyphCache<'_> {
+pub fn load_cache(font_bytes: &'static [u8]) -> GlyphCache<'static> {
     GlyphCache::from_font(load_font(font_bytes), (), TextureSettings::new())
 }
 
@@ -41,7 +41,7 @@ pub struct TextDraw<'a> {
 impl<'a> TextDraw<'a> {
     pub fn new(cache: GlyphCache<'a>) -> Self {
         TextDraw {
-            cache: cache,
+            cache,
         }
     }
 
@@ -59,8 +59,6 @@ impl<'a> TextDraw<'a> {
         Text::new_color(*color, *size)
             .draw(&text.to_string(), &mut self.cache, &DrawState::default(), transformer, gl)
             .unwrap();
-
-        drop(transformer);
     }
 
     pub fn draw_center(&mut self, text: &String, color: &ColorComps, size: &u32, bounds: &[f64; 2], ctx: &Context, gl: &mut GlGraphics) {
@@ -70,10 +68,6 @@ impl<'a> TextDraw<'a> {
         let y = (bounds[1] / 2.0) - half_size;
 
         self.draw(text, color, &[x, y], size, ctx, gl);
-
-        drop(x);
-        drop(y);
-        drop(half_size);
     }
 }
 
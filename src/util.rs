@@ -1,11 +1,44 @@
-use rand::{thread_rng, Rng, distributions::uniform::SampleUniform};
+use std::collections::BTreeMap;
+
+use rand::{Rng, SeedableRng, distributions::uniform::SampleUniform};
+use rand_chacha::ChaCha8Rng;
+
+// the game's random source, seeded so a run can be played back exactly.
+pub type GameRng = ChaCha8Rng;
+
+pub fn new_rng(seed: u64) -> GameRng {
+    GameRng::seed_from_u64(seed)
+}
+
+// a fresh seed for when the player didn't ask for one.
+pub fn random_seed() -> u64 {
+    rand::thread_rng().gen()
+}
 
 // now with generic type
-pub fn get_random_number<E: SampleUniform + std::cmp::PartialOrd>(min: E, max: E) -> E {
-    let mut rng = thread_rng();
-    let result = rng.gen_range(min..max);
+pub fn get_random_number<E: SampleUniform + std::cmp::PartialOrd>(rng: &mut GameRng, min: E, max: E) -> E {
+    rng.gen_range(min..max)
+}
 
-    drop(rng);
-    result
+// a number out of a config's `[min, max]`, which may be a fixed `[value, value]`.
+pub fn get_random_in<E: SampleUniform + std::cmp::PartialOrd + Copy>(rng: &mut GameRng, range: [E; 2]) -> E {
+    if range[0] < range[1] {
+        rng.gen_range(range[0]..range[1])
+    } else {
+        range[0]
+    }
 }
 
+// one of the keys of `mix`, more likely the higher its weight, none when `mix` is empty.
+pub fn get_random_weighted<K: Copy>(rng: &mut GameRng, mix: &BTreeMap<K, f64>) -> Option<K> {
+    let total: f64 = mix.values().sum();
+    let mut roll = get_random_number::<f64>(rng, 0.0, total);
+    for (key, weight) in mix {
+        if roll < *weight {
+            return Some(*key);
+        }
+        roll -= weight;
+    }
+    // only reached through rounding errors
+    mix.keys().next_back().copied()
+}
@@ -0,0 +1,196 @@
+// Wave progression: a rest of `delay` seconds, then a wave of monsters, and the
+// next rest once all of them are dead. After the last wave comes the boss, if there
+// is one, and the run is won once it is beaten too.
+
+use std::f64::consts::PI;
+
+use piston::window::Size;
+
+use crate::config::{BossConfig, EnemyConfig, SpawnPattern, WaveConfig};
+use crate::ecs::{Entities};
+use crate::geom::{Position, Rect};
+use crate::schemas::enemy::{Enemy, EnemyType};
+use crate::util::{get_random_number, GameRng};
+
+// keep spawns this far away from the walls, so the monsters don't start half inside them.
+const SPAWN_MARGIN: f64 = 20.0;
+
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum WaveEvent {
+    Spawn(usize), // the wave at this index has to come now
+    Boss, // the boss has to come now
+    Finished, // the last wave, or the boss after it, was beaten
+}
+
+pub struct Waves {
+    pub current: usize, // index of the wave that is coming, or being fought
+    pub countdown: f64, // secs of rest left before it comes
+    pub active: bool, // its monsters are out
+    boss: Option<f64>, // secs of rest before the boss, none if there isn't any
+}
+
+impl Waves {
+    pub fn new(waves: &[WaveConfig], boss: &BossConfig) -> Self {
+        let boss = if boss.enabled { Some(boss.delay) } else { None };
+        Waves {
+            current: 0,
+            countdown: waves.first().map(|wave| wave.delay).or(boss).unwrap_or(0.0),
+            active: false,
+            boss,
+        }
+    }
+
+    // the number to show the player, starting at 1.
+    pub fn number(&self) -> usize {
+        self.current + 1
+    }
+
+    // resting between two waves, so "Wave N" can be shown.
+    pub fn is_resting(&self) -> bool {
+        !self.active
+    }
+
+    // the boss is coming, or being fought.
+    pub fn is_boss(&self, waves: &[WaveConfig]) -> bool {
+        self.boss.is_some() && self.current == waves.len()
+    }
+
+    pub fn update(&mut self, dt: f64, waves: &[WaveConfig], monsters_left: usize) -> Option<WaveEvent> {
+        let stages = waves.len() + usize::from(self.boss.is_some());
+        if self.current >= stages {
+            return None;
+        }
+
+        if !self.active {
+            self.countdown -= dt;
+            if self.countdown <= 0.0 {
+                self.active = true;
+                return Some(if self.is_boss(waves) { WaveEvent::Boss } else { WaveEvent::Spawn(self.current) });
+            }
+            return None;
+        }
+
+        if monsters_left > 0 {
+            return None;
+        }
+
+        self.active = false;
+        self.current += 1;
+        match (waves.get(self.current), self.boss) {
+            (Some(wave), _) => {
+                self.countdown = wave.delay;
+                None
+            },
+            (None, Some(delay)) if self.current < stages => {
+                self.countdown = delay;
+                None
+            },
+            _ => Some(WaveEvent::Finished),
+        }
+    }
+}
+
+// put every monster of `wave` into the arena.
+pub fn spawn_wave(entities: &mut Entities, wave: &WaveConfig, config: &EnemyConfig, size: Size, zones: &[Rect], rng: &mut GameRng) {
+    for pos in spawn_points(wave.pattern, wave.count, size, zones, rng) {
+        let enemy_type = EnemyType::pick(&wave.mix, rng);
+        Enemy::spawn(entities, pos, enemy_type, wave.difficulty, config, rng);
+    }
+}
+
+// without any `zones`, the zones pattern falls back to anywhere in the arena.
+pub fn spawn_points(pattern: SpawnPattern, count: usize, size: Size, zones: &[Rect], rng: &mut GameRng) -> Vec<Position> {
+    let (min, max_x, max_y) = (SPAWN_MARGIN, size.width - SPAWN_MARGIN, size.height - SPAWN_MARGIN);
+    match pattern {
+        SpawnPattern::Zones if !zones.is_empty() => (0..count)
+            .map(|_| {
+                let zone = &zones[get_random_number::<usize>(rng, 0, zones.len())];
+                Position::new(
+                    zone.x + get_random_number::<f64>(rng, 0.0, 1.0) * zone.width,
+                    zone.y + get_random_number::<f64>(rng, 0.0, 1.0) * zone.height,
+                )
+            })
+            .collect(),
+        SpawnPattern::Random | SpawnPattern::Zones => (0..count)
+            .map(|_| Position::new(
+                get_random_number::<f64>(rng, 0.0, size.width),
+                get_random_number::<f64>(rng, 0.0, size.height),
+            ))
+            .collect(),
+        SpawnPattern::Edges => (0..count)
+            .map(|_| {
+                let along = get_random_number::<f64>(rng, 0.0, 1.0);
+                match get_random_number::<u8>(rng, 0, 4) {
+                    0 => Position::new(min + (max_x - min) * along, min),
+                    1 => Position::new(max_x, min + (max_y - min) * along),
+                    2 => Position::new(min + (max_x - min) * along, max_y),
+                    _ => Position::new(min, min + (max_y - min) * along),
+                }
+            })
+            .collect(),
+        SpawnPattern::Ring => {
+            let center = Position::new(size.width / 2.0, size.height / 2.0);
+            let radius = (size.width.min(size.height) / 2.0 - SPAWN_MARGIN).max(0.0);
+            let start = get_random_number::<f64>(rng, 0.0, 2.0 * PI);
+            (0..count)
+                .map(|index| center + Position::from_angle(start + 2.0 * PI * index as f64 / count as f64, radius))
+                .collect()
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rests_then_fights_every_wave() {
+        let waves = vec![
+            WaveConfig { delay: 1.0, ..WaveConfig::default() },
+            WaveConfig { delay: 2.0, ..WaveConfig::default() },
+        ];
+        let mut progress = Waves::new(&waves, &BossConfig { enabled: false, ..BossConfig::default() });
+
+        assert_eq!(progress.update(0.5, &waves, 0), None);
+        assert_eq!(progress.update(0.5, &waves, 0), Some(WaveEvent::Spawn(0)));
+        // not over while monsters are left
+        assert_eq!(progress.update(5.0, &waves, 3), None);
+        assert_eq!(progress.update(0.1, &waves, 0), None);
+        assert!(progress.is_resting());
+        assert_eq!(progress.number(), 2);
+        assert_eq!(progress.update(2.0, &waves, 0), Some(WaveEvent::Spawn(1)));
+        assert_eq!(progress.update(0.1, &waves, 0), Some(WaveEvent::Finished));
+        assert_eq!(progress.update(0.1, &waves, 0), None);
+    }
+
+    #[test]
+    fn the_boss_comes_after_the_last_wave() {
+        let waves = vec![WaveConfig { delay: 1.0, ..WaveConfig::default() }];
+        let mut progress = Waves::new(&waves, &BossConfig { delay: 2.0, ..BossConfig::default() });
+
+        assert_eq!(progress.update(1.0, &waves, 0), Some(WaveEvent::Spawn(0)));
+        assert!(!progress.is_boss(&waves));
+        assert_eq!(progress.update(0.1, &waves, 0), None);
+        assert!(progress.is_boss(&waves));
+        assert_eq!(progress.update(1.0, &waves, 0), None);
+        assert_eq!(progress.update(1.0, &waves, 0), Some(WaveEvent::Boss));
+        // its minions count as well
+        assert_eq!(progress.update(0.1, &waves, 4), None);
+        assert_eq!(progress.update(0.1, &waves, 0), Some(WaveEvent::Finished));
+    }
+
+    #[test]
+    fn spawn_points_stay_in_the_arena() {
+        let size = Size::from([500.0, 300.0]);
+        let mut rng = crate::util::new_rng(1);
+        let zones = [Rect::new(400.0, 100.0, 50.0, 50.0)];
+        for pattern in [SpawnPattern::Random, SpawnPattern::Edges, SpawnPattern::Ring, SpawnPattern::Zones] {
+            let points = spawn_points(pattern, 12, size, &zones, &mut rng);
+            assert_eq!(points.len(), 12);
+            assert!(points.iter().all(|pos| (0.0..=size.width).contains(&pos.x) && (0.0..=size.height).contains(&pos.y)));
+        }
+
+        let points = spawn_points(SpawnPattern::Zones, 12, size, &zones, &mut rng);
+        assert!(points.iter().all(|pos| zones[0].contains(pos)));
+    }
+}
@@ -0,0 +1,157 @@
+// The player's guns: what a shot does comes from the weapon's config, the rounds
+// in the magazine and in reserve belong to the weapon, so switching doesn't refill it.
+
+use crate::config::{WeaponConfig};
+use crate::util::{get_random_number, GameRng};
+
+pub struct Weapon {
+    pub config: WeaponConfig,
+    pub rounds: u32, // left in the magazine
+    pub reserve: Option<u32>, // carried besides the magazine, none for endless
+    pub reloading: Option<f64>, // secs until the reload is done
+    pub cooldown: f64, // secs until it can fire again, set by its fire rate
+}
+
+impl Weapon {
+    pub fn new(config: &WeaponConfig) -> Self {
+        Weapon { config: config.clone(), rounds: config.magazine, reserve: config.reserve, reloading: None, cooldown: 0.0 }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rounds == 0
+    }
+
+    // nothing left in the magazine or in reserve.
+    pub fn is_dry(&self) -> bool {
+        self.is_empty() && self.reserve == Some(0)
+    }
+
+    // a reload would put rounds into the magazine.
+    pub fn can_reload(&self) -> bool {
+        self.reloading.is_none() && self.rounds < self.config.magazine && self.reserve != Some(0)
+    }
+
+    // start reloading, also with rounds still in the magazine.
+    pub fn reload(&mut self) {
+        if self.can_reload() {
+            self.reloading = Some(self.config.reload_time);
+        }
+    }
+
+    pub fn cancel_reload(&mut self) {
+        self.reloading = None;
+    }
+
+    // how far (0..1) the reload is, none when not reloading.
+    pub fn reload_progress(&self) -> Option<f64> {
+        self.reloading.map(|left| 1.0 - left / self.config.reload_time)
+    }
+
+    // the fire rate allows another shot.
+    pub fn is_ready(&self) -> bool {
+        self.cooldown <= 0.0
+    }
+
+    // count the cooldown and the reload down, and top the magazine up from the reserve once the reload is done.
+    pub fn update(&mut self, dt: f64) {
+        if self.cooldown > 0.0 {
+            self.cooldown -= dt;
+        }
+
+        let Some(left) = self.reloading else {
+            return;
+        };
+        if left > dt {
+            self.reloading = Some(left - dt);
+            return;
+        }
+
+        self.reloading = None;
+        let missing = self.config.magazine - self.rounds;
+        let taken = self.reserve.map_or(missing, |reserve| reserve.min(missing));
+        self.rounds += taken;
+        if let Some(reserve) = &mut self.reserve {
+            *reserve -= taken;
+        }
+    }
+
+    // add `magazines` full magazines to a limited reserve.
+    pub fn refill(&mut self, magazines: u32) {
+        if let Some(reserve) = &mut self.reserve {
+            *reserve = reserve.saturating_add(self.config.magazine.saturating_mul(magazines));
+        }
+    }
+
+    // use up a round and get the angle of every bullet of the shot, none if it is empty.
+    // Firing with rounds left breaks off a reload.
+    pub fn fire(&mut self, aim: f64, rng: &mut GameRng) -> Vec<f64> {
+        if self.is_empty() {
+            return Vec::new();
+        }
+
+        self.cancel_reload();
+        self.rounds -= 1;
+        let spread = self.config.spread;
+        (0..self.config.pellets)
+            .map(|_| if spread > 0.0 { aim + get_random_number::<f64>(rng, -spread, spread) } else { aim })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::{new_rng};
+
+    fn shotgun() -> WeaponConfig {
+        WeaponConfig { reserve: Some(8), reload_time: 1.0, ..WeaponConfig::defaults()[1].clone() }
+    }
+
+    #[test]
+    fn a_shot_uses_one_round_for_all_pellets() {
+        let shotgun = shotgun();
+        let mut weapon = Weapon::new(&shotgun);
+        let mut rng = new_rng(1);
+
+        let angles = weapon.fire(1.0, &mut rng);
+        assert_eq!(angles.len(), shotgun.pellets as usize);
+        assert!(angles.iter().all(|angle| (angle - 1.0).abs() <= shotgun.spread));
+        assert_eq!(weapon.rounds, shotgun.magazine - 1);
+
+        weapon.rounds = 0;
+        assert!(weapon.fire(1.0, &mut rng).is_empty());
+    }
+
+    #[test]
+    fn reloads_take_time_and_come_out_of_the_reserve() {
+        let mut weapon = Weapon::new(&shotgun());
+        let mut rng = new_rng(1);
+        // a full magazine has nothing to reload
+        weapon.reload();
+        assert_eq!(weapon.reloading, None);
+
+        // a tactical reload only takes what is missing
+        weapon.fire(0.0, &mut rng);
+        weapon.fire(0.0, &mut rng);
+        weapon.reload();
+        weapon.update(0.5);
+        assert_eq!(weapon.reload_progress(), Some(0.5));
+        assert_eq!(weapon.rounds, 4);
+        weapon.update(0.5);
+        assert_eq!((weapon.rounds, weapon.reserve), (6, Some(6)));
+
+        // shooting breaks it off
+        weapon.fire(0.0, &mut rng);
+        weapon.reload();
+        weapon.fire(0.0, &mut rng);
+        weapon.update(1.0);
+        assert_eq!((weapon.rounds, weapon.reserve), (4, Some(6)));
+
+        // the last of the reserve
+        weapon.reserve = Some(1);
+        weapon.reload();
+        weapon.update(1.0);
+        assert_eq!((weapon.rounds, weapon.reserve), (5, Some(0)));
+        assert!(!weapon.can_reload());
+    }
+}
@@ -0,0 +1,704 @@
+use std::f64::consts::PI;
+
+use piston::window::Size;
+
+use crate::collider::{Collider};
+use crate::collision::{Grid};
+use crate::config::{GameConfig, WeaponConfig};
+use crate::ecs::{Entities, Entity};
+use crate::ecs::components::{Explosive, Invulnerable, Piercing, Team};
+use crate::ecs::systems::{self, Hit, Shot, Summon, Wound};
+use crate::schemas::player::{Player};
+use crate::schemas::bullet::{Bullet};
+use crate::schemas::enemy::{Enemy, EnemyType};
+use crate::schemas::boss::{Boss};
+use crate::schemas::obstacle::{Obstacle};
+use crate::schemas::pickup::{Pickup, PickupKind};
+use crate::level::{Level};
+use crate::geom::{Direction, Position};
+use crate::util::{get_random_in, get_random_number, get_random_weighted, new_rng, GameRng};
+use crate::waves::{self, Waves, WaveEvent};
+
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum GameStatus {
+    Fight, // fighting mode
+    Win, // every wave has been beaten
+    Lose, // could be dead
+}
+
+// everything the world can be told to do, independent of the device it came from.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum GameInput {
+    StartMove(Direction),
+    StopMove(Direction),
+    Stick(f64, f64), // analog movement, each axis in -1..1
+    Aim(f64, f64), // point the gun at this spot of the arena
+    StartFire, // the trigger follows the fire button being held
+    StopFire,
+    Reload,
+    SelectWeapon(usize), // the weapon in this slot, from 0
+    NextWeapon,
+    PrevWeapon,
+    Restart,
+    Pause, // toggles
+}
+
+// what happened to the player during the last tick, for the front-end to react to.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum GameEvent {
+    Died { at: Position, lives: u8 }, // lives left, none means the run is lost
+    Respawned { at: Position },
+}
+
+// the simulation always runs in steps of <TICK_DT>, whatever the frame rate is.
+pub const TICK_RATE: f64 = 120.0; // ticks/sec
+pub const TICK_DT: f64 = 1.0 / TICK_RATE;
+
+// px from a dead splitter that its parts start at.
+const SPLIT_DISTANCE: f64 = 15.0;
+// px from the boss that its minions start at.
+const SUMMON_DISTANCE: f64 = 70.0;
+// random spots tried for a respawn when the level's start is too close to the monsters.
+const SPAWN_TRIES: usize = 32;
+
+// The whole game state and rules, without any window or renderer.
+// A front-end feeds it inputs and a `dt`, then draws whatever is inside.
+pub struct GameWorld {
+    pub entities: Entities,
+    pub player: Entity,
+    pub size: Size, // arena size
+    pub level: Level,
+
+    // game state
+    pub status: GameStatus,
+    pub waves: Waves,
+    pub paused: bool,
+    pub config: GameConfig,
+    pub seed: u64,
+    pub events: Vec<GameEvent>, // of the last tick
+    rng: GameRng,
+    grid: Grid, // targets by area, rebuilt every tick
+    shots: Vec<Shot>,
+    hits: Vec<Hit>,
+    wounds: Vec<Wound>,
+    summons: Vec<Summon>,
+}
+
+impl GameWorld {
+    // the same config, seed and inputs always play out the same run.
+    pub fn new(config: GameConfig, level: Level, seed: u64) -> Self {
+        let mut entities = Entities::new();
+        let player = GameWorld::populate(&mut entities, "Tono", &level, &config);
+
+        GameWorld {
+            entities,
+            player,
+            size: Size::from([level.width, level.height]),
+            grid: Grid::new(level.width, level.height),
+            status: GameStatus::Fight,
+            waves: Waves::new(level.waves(&config), &config.boss),
+            level,
+            paused: false,
+            config,
+            seed,
+            events: Vec::new(),
+            rng: new_rng(seed),
+            shots: Vec::new(),
+            hits: Vec::new(),
+            wounds: Vec::new(),
+            summons: Vec::new(),
+        }
+    }
+
+    // the level's walls and the player at its start, returns the player.
+    fn populate(entities: &mut Entities, name: &str, level: &Level, config: &GameConfig) -> Entity {
+        for obstacle in &level.obstacles {
+            Obstacle::spawn(entities, obstacle);
+        }
+
+        Player::spawn(entities, name, level.start(), &config.player, &config.weapons)
+    }
+
+    pub fn reset(&mut self) {
+        self.entities = Entities::new();
+        self.player = GameWorld::populate(&mut self.entities, "You", &self.level, &self.config);
+
+        self.status = GameStatus::Fight;
+        self.waves = Waves::new(self.level.waves(&self.config), &self.config.boss);
+        self.paused = false;
+        self.events.clear();
+    }
+
+    // the player's own state, the player entity lives as long as the world.
+    pub fn player(&self) -> &Player {
+        self.entities.players.get(self.player).expect("the player entity is never despawned")
+    }
+
+    fn player_mut(&mut self) -> &mut Player {
+        self.entities.players.get_mut(self.player).expect("the player entity is never despawned")
+    }
+
+    pub fn player_health(&self) -> f64 {
+        self.entities.healths.get(self.player).map_or(0.0, |health| health.value)
+    }
+
+    pub fn player_pos(&self) -> Position {
+        self.entities.transforms.get(self.player).map_or(Position::zero(), |transform| transform.pos)
+    }
+
+    // put the player back at `pos`, standing still.
+    fn teleport_player(&mut self, pos: Position) {
+        if let Some(transform) = self.entities.transforms.get_mut(self.player) {
+            transform.teleport(pos);
+        }
+        if let Some(velocity) = self.entities.velocities.get_mut(self.player) {
+            velocity.0 = Position::zero();
+        }
+    }
+
+    // apply the inputs, then advance the world by `dt` seconds.
+    pub fn step(&mut self, dt: f64, inputs: &[GameInput]) {
+        for input in inputs {
+            self.input(*input);
+        }
+
+        self.update(dt);
+    }
+
+    pub fn input(&mut self, input: GameInput) {
+        // no shooting while paused, movement keys are still tracked so nothing gets stuck.
+        if self.paused && matches!(input, GameInput::StartFire | GameInput::Reload | GameInput::SelectWeapon(_) | GameInput::NextWeapon | GameInput::PrevWeapon) {
+            return;
+        }
+
+        match input {
+            GameInput::StartMove(direction) => self.player_mut().start_move(direction),
+            GameInput::StopMove(direction) => self.player_mut().stop_move(direction),
+            GameInput::Stick(x, y) => self.player_mut().analog_move(x, y),
+            GameInput::Aim(x, y) => {
+                let pos = self.player_pos();
+                self.player_mut().aim_at(pos, Position::new(x, y));
+            },
+            GameInput::StartFire => self.player_mut().press_trigger(),
+            GameInput::StopFire => self.player_mut().release_trigger(),
+            GameInput::Reload => self.player_mut().weapon_mut().reload(),
+            GameInput::SelectWeapon(slot) => self.player_mut().select_weapon(slot),
+            GameInput::NextWeapon => self.player_mut().cycle_weapon(1),
+            GameInput::PrevWeapon => self.player_mut().cycle_weapon(-1),
+            GameInput::Restart => self.reset(),
+            GameInput::Pause => self.paused = !self.paused,
+        }
+    }
+
+    // the living boss with its health, for the HUD.
+    pub fn boss(&self) -> Option<(&Boss, f64)> {
+        self.entities.bosses.iter().next().map(|(entity, boss)| {
+            (boss, self.entities.healths.get(entity).map_or(0.0, |health| (health.value / health.max).max(0.0)))
+        })
+    }
+
+    // put `count` enemies in a circle `distance` px around `pos`.
+    fn spawn_around(&mut self, pos: Position, distance: f64, enemy_type: EnemyType, count: usize, difficulty: f64) {
+        let start = get_random_number::<f64>(&mut self.rng, 0.0, 2.0 * PI);
+        for index in 0..count {
+            let angle = start + 2.0 * PI * index as f64 / count as f64;
+            let at = pos + Position::from_angle(angle, distance);
+            Enemy::spawn(&mut self.entities, at, enemy_type, difficulty, &self.config.enemy, &mut self.rng);
+        }
+    }
+
+    // spawn what a dying splitter breaks up into, around where it was.
+    fn split(&mut self, entity: Entity) {
+        let (Some(splitter), Some(transform), Some(enemy)) = (
+            self.entities.splitters.get(entity).copied(), self.entities.transforms.get(entity), self.entities.enemies.get(entity),
+        ) else {
+            return;
+        };
+        let (pos, difficulty) = (transform.pos, enemy.difficulty);
+
+        self.spawn_around(pos, SPLIT_DISTANCE, splitter.into, splitter.count, difficulty);
+        self.entities.splitters.remove(entity);
+    }
+
+    // one bullet of the player's `weapon`, its damage multiplied by `boost`.
+    fn fire_bullet(&mut self, pos: Position, angle: f64, weapon: &WeaponConfig, boost: f64) {
+        let damage = get_random_in(&mut self.rng, weapon.damage) * boost as f32;
+        let bullet = Bullet::spawn(&mut self.entities, pos, angle, weapon.speed, self.config.bullet.lifetime, damage, Team::Player);
+        if weapon.pierce > 0 {
+            self.entities.piercings.insert(bullet, Piercing { left: weapon.pierce, hit: Vec::new() });
+        }
+        if let Some(splash) = weapon.splash {
+            self.entities.explosives.insert(bullet, Explosive { radius: splash.radius });
+        }
+    }
+
+    // a killed monster may leave a pickup where it fell.
+    fn drop_pickup(&mut self, entity: Entity) {
+        let Some(pos) = self.entities.transforms.get(entity).map(|transform| transform.pos) else {
+            return;
+        };
+        let pickups = &self.config.pickups;
+        if get_random_number::<f64>(&mut self.rng, 0.0, 1.0) >= pickups.chance {
+            return;
+        }
+        if let Some(kind) = get_random_weighted(&mut self.rng, &pickups.mix) {
+            Pickup::spawn(&mut self.entities, pos, kind, pickups.lifetime);
+        }
+    }
+
+    // whatever the player walks over is used up at once.
+    fn collect_pickups(&mut self) {
+        let collected: Vec<(Entity, PickupKind)> = self.entities.pickups.iter()
+            .filter(|(pickup, _)| self.entities.touching(*pickup, self.player))
+            .map(|(pickup, kind)| (pickup, kind.kind))
+            .collect();
+
+        for (pickup, kind) in collected {
+            self.entities.despawn(pickup);
+            self.use_pickup(kind);
+        }
+    }
+
+    fn use_pickup(&mut self, kind: PickupKind) {
+        let pickups = self.config.pickups.clone();
+        match kind {
+            PickupKind::Health => {
+                if let Some(health) = self.entities.healths.get_mut(self.player) {
+                    health.value = (health.value + pickups.health).min(health.max);
+                }
+            },
+            PickupKind::Ammo => {
+                for weapon in &mut self.player_mut().weapons {
+                    weapon.refill(pickups.ammo);
+                }
+            },
+            PickupKind::Life => {
+                let player = self.player_mut();
+                player.life = player.life.saturating_add(1);
+            },
+            PickupKind::RapidFire | PickupKind::Shield | PickupKind::Damage | PickupKind::Speed => {
+                self.player_mut().add_buff(kind, pickups.duration, pickups.boost(kind));
+            },
+        }
+    }
+
+    // The level's start, unless a monster is closer than `player.safe_distance` to it,
+    // then the free spot furthest from the monsters out of a few random ones.
+    fn safe_spawn(&mut self) -> Position {
+        let monsters: Vec<Position> = self.entities.enemies.iter().map(|(entity, _)| entity)
+            .chain(self.entities.bosses.iter().map(|(entity, _)| entity))
+            .filter_map(|entity| self.entities.transforms.get(entity).map(|transform| transform.pos))
+            .collect();
+        let room = |pos: &Position| monsters.iter().map(|monster| monster.distance(pos)).fold(f64::INFINITY, f64::min);
+
+        let start = self.level.start();
+        let mut best = (start, room(&start));
+        if best.1 >= self.config.player.safe_distance {
+            return start;
+        }
+
+        let margin = self.entities.confined.get(self.player).map_or(0.0, |confined| confined.margin);
+        let body = Collider::Circle { radius: margin };
+        for _ in 0..SPAWN_TRIES {
+            let pos = Position::new(
+                get_random_number::<f64>(&mut self.rng, 0.0, self.size.width),
+                get_random_number::<f64>(&mut self.rng, 0.0, self.size.height),
+            );
+            let blocked = self.entities.obstacles.iter().any(|(obstacle, _)| {
+                match (self.entities.transforms.get(obstacle), self.entities.colliders.get(obstacle)) {
+                    (Some(transform), Some(collider)) => body.overlaps(&pos, &collider.turned(transform.angle), &transform.pos),
+                    _ => false,
+                }
+            });
+            let distance = room(&pos);
+            if !blocked && distance > best.1 {
+                best = (pos, distance);
+            }
+        }
+
+        best.0
+    }
+
+    // lose a life, then come back with full health at a safe spot and can't be hurt for a moment.
+    fn kill_player(&mut self) {
+        let at = self.player_pos();
+        let player = self.player_mut();
+        player.life = player.life.saturating_sub(1);
+        player.buffs.clear();
+        let lives = player.life;
+        self.events.push(GameEvent::Died { at, lives });
+        if lives == 0 {
+            self.status = GameStatus::Lose;
+            return;
+        }
+
+        let spawn = self.safe_spawn();
+        self.teleport_player(spawn);
+        if let Some(health) = self.entities.healths.get_mut(self.player) {
+            health.value = health.max;
+        }
+        let invulnerability = self.config.player.invulnerability;
+        if invulnerability > 0.0 {
+            self.entities.invulnerables.insert(self.player, Invulnerable(invulnerability));
+        }
+        self.events.push(GameEvent::Respawned { at: spawn });
+    }
+
+    // the boss comes in on the other side of the arena from the player.
+    fn spawn_boss(&mut self) {
+        let player = self.player_pos();
+        let pos = Position::new(self.size.width - player.x, self.size.height - player.y);
+        let difficulty = self.level.waves(&self.config).last().map_or(1.0, |wave| wave.difficulty);
+        Boss::spawn(&mut self.entities, pos, &self.config.boss, difficulty, &mut self.rng);
+    }
+
+    pub fn update(&mut self, dt: f64) {
+        if self.paused {
+            return;
+        }
+
+        let size = self.size;
+        self.events.clear();
+
+        let pos = self.player_pos();
+        let player = self.entities.players.get_mut(self.player).expect("the player entity is never despawned");
+        // every weapon cools down, also the ones put away.
+        for weapon in &mut player.weapons {
+            weapon.update(dt);
+        }
+        player.update_buffs(dt);
+        // shoot as often as the weapon's fire rate allows while the player wants to.
+        if player.weapon().is_ready() && player.wants_to_fire() {
+            let angles = player.fire(&mut self.rng);
+            let config = player.weapon().config.clone();
+            let (rapid_fire, damage) = (player.boost(PickupKind::RapidFire), player.boost(PickupKind::Damage));
+            // keep what is left of this tick, so the rate doesn't depend on the tick length.
+            if !angles.is_empty() {
+                player.weapon_mut().cooldown += config.fire_cooldown / rapid_fire;
+            }
+            for angle in angles {
+                self.fire_bullet(pos, angle, &config, damage);
+            }
+        }
+
+        systems::steering(&mut self.entities, dt);
+        systems::movement(&mut self.entities, dt, size);
+        systems::obstacles(&mut self.entities);
+        systems::lifetime(&mut self.entities, dt);
+
+        // only works in fight mode.
+        if self.status != GameStatus::Fight {
+            return;
+        }
+
+        self.collect_pickups();
+
+        // exploders blow up as soon as they reach the player, the others hurt for as long as they touch it.
+        let monsters: Vec<Entity> = self.entities.enemies.iter().map(|(entity, _)| entity)
+            .chain(self.entities.bosses.iter().map(|(entity, _)| entity))
+            .filter(|monster| self.entities.touching(*monster, self.player))
+            .collect();
+        let invulnerable = !self.entities.can_be_hurt(self.player);
+        let mut blasts = Vec::new();
+        let mut contact = 0.0;
+        for monster in monsters {
+            if self.entities.explosives.contains(monster) {
+                systems::explode(&mut self.entities, monster, &mut blasts);
+            } else if !invulnerable {
+                contact += self.entities.contacts.get(monster).map_or(0.0, |contact| contact.0) * dt;
+            }
+        }
+        if let Some(health) = self.entities.healths.get_mut(self.player) {
+            health.value -= contact;
+        }
+
+        // shooters fire at the player, their bullets hurt as much as the shooter does.
+        systems::aiming(&mut self.entities, dt, self.config.bullet.speed, &mut self.shots);
+        systems::boss_attacks(&mut self.entities, dt, &mut self.shots, &mut self.summons);
+        for shot in &self.shots {
+            let spread = self.entities.shooters.get(shot.shooter).map_or(0.0, |shooter| shooter.spread);
+            let angle = if spread > 0.0 { shot.angle + get_random_number::<f64>(&mut self.rng, -spread, spread) } else { shot.angle };
+            let damage = self.entities.damages.get(shot.shooter).copied();
+            let bullet = &self.config.bullet;
+            Bullet::spawn(&mut self.entities, shot.from, angle, bullet.speed, bullet.lifetime, damage.map_or(0.0, |damage| damage.0), Team::Monster);
+        }
+
+        let summons = std::mem::take(&mut self.summons);
+        for summon in &summons {
+            let difficulty = self.entities.bosses.get(summon.boss).map_or(1.0, |boss| boss.difficulty);
+            self.spawn_around(summon.pos, SUMMON_DISTANCE, summon.enemy_type, summon.count, difficulty);
+        }
+        self.summons = summons;
+
+        systems::collision(&self.entities, &mut self.grid, &mut self.hits);
+        systems::damage(&mut self.entities, &self.hits, &mut self.wounds);
+
+        let mut score = 0.0;
+        let mut killed = Vec::new();
+        for wound in &self.wounds {
+            // if the monster is killed by the player's bullet, the damage of that bullet is added as score.
+            if wound.team == Team::Player && wound.killed {
+                score += wound.amount;
+            }
+            if wound.killed {
+                killed.push(wound.target);
+            }
+        }
+        self.player_mut().score += score;
+
+        // what the dead leave behind: pickups, splitters break up, exploders blow up where they fell.
+        for entity in killed {
+            if self.entities.enemies.contains(entity) {
+                self.drop_pickup(entity);
+            }
+            self.split(entity);
+            systems::explode(&mut self.entities, entity, &mut blasts);
+        }
+
+        systems::reap(&mut self.entities);
+
+        // the player dies once, however many things hurt it this tick.
+        if self.player_health() <= 0.0 {
+            self.kill_player();
+            if self.status != GameStatus::Fight {
+                return;
+            }
+        }
+
+        // the next wave comes once this one is dead, the run is won after the last one.
+        let waves = self.level.waves(&self.config);
+        let monsters_left = self.entities.enemies.len() + self.entities.bosses.len();
+        match self.waves.update(dt, waves, monsters_left) {
+            Some(WaveEvent::Spawn(index)) => {
+                waves::spawn_wave(&mut self.entities, &waves[index], &self.config.enemy, size, &self.level.spawn_zones, &mut self.rng);
+            },
+            Some(WaveEvent::Boss) => self.spawn_boss(),
+            Some(WaveEvent::Finished) => self.status = GameStatus::Win,
+            None => {},
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // bullets the player fires in `secs` with the trigger held down the whole time.
+    fn shots_while_held(slot: usize, secs: f64) -> u32 {
+        let mut world = new_world(1);
+        world.input(GameInput::SelectWeapon(slot));
+        world.input(GameInput::StartFire);
+
+        let weapon = world.player().weapon();
+        let before = weapon.rounds;
+        for _ in 0..(secs * TICK_RATE).round() as usize {
+            world.update(TICK_DT);
+        }
+        before - world.player().weapon().rounds
+    }
+
+    fn new_world(seed: u64) -> GameWorld {
+        let config = GameConfig::default();
+        let level = Level::open_field(&config);
+        GameWorld::new(config, level, seed)
+    }
+
+    #[test]
+    fn runs_without_a_window() {
+        let mut world = new_world(1);
+        for _ in 0..10 {
+            world.step(TICK_DT, &[]);
+        }
+        let rounds = world.player().weapon().rounds;
+        world.step(TICK_DT, &[GameInput::StartFire]);
+
+        assert_eq!(world.entities.bullets.len(), 1);
+        assert_eq!(world.player().weapon().rounds, rounds - 1);
+        assert_eq!(world.status, GameStatus::Fight);
+    }
+
+    // a few secs of walking and shooting, then what every enemy and the player ended up with.
+    fn play(seed: u64) -> (Vec<Position>, Position, f64, f64) {
+        let mut world = new_world(seed);
+        let inputs = [
+            vec![GameInput::StartMove(Direction::SOUTH), GameInput::SelectWeapon(2), GameInput::StartFire],
+            vec![GameInput::Aim(400.0, 300.0)],
+            vec![GameInput::StopMove(Direction::SOUTH), GameInput::StartMove(Direction::EAST)],
+        ];
+        for tick in 0..(6.0 * TICK_RATE) as usize {
+            let inputs = if tick % 120 == 0 { &inputs[(tick / 120) % inputs.len()][..] } else { &[] };
+            world.step(TICK_DT, inputs);
+        }
+
+        let enemies = world.entities.enemies.iter()
+            .filter_map(|(entity, _)| world.entities.transforms.get(entity).map(|transform| transform.pos))
+            .collect();
+        (enemies, world.player_pos(), world.player_health(), world.player().score)
+    }
+
+    #[test]
+    fn the_same_seed_and_inputs_play_the_same_run() {
+        let run = play(42);
+        assert!(!run.0.is_empty());
+        assert_eq!(run, play(42));
+        assert_ne!(run.0, play(43).0);
+    }
+
+    #[test]
+    fn the_gun_keeps_pointing_at_the_cursor_while_walking() {
+        let mut world = new_world(1);
+        world.update(TICK_DT); // settle the player inside the arena
+        let target = world.player_pos() + Position::new(100.0, 0.0);
+        world.input(GameInput::Aim(target.x, target.y));
+        assert_eq!(world.player().aim, 0.0);
+
+        world.input(GameInput::StartMove(Direction::SOUTH));
+        for _ in 0..(0.5 * TICK_RATE) as usize {
+            world.update(TICK_DT);
+        }
+
+        let expected = (target - world.player_pos()).angle();
+        assert!(world.player_pos().y > target.y + 100.0);
+        assert!((world.player().aim - expected).abs() < 0.05);
+        assert_eq!(world.entities.transforms.get(world.player).unwrap().angle, world.player().aim);
+    }
+
+    // whether a shot at a shielded enemy 150 px east, facing `facing`, hurts it.
+    fn shot_hurts_shielded(facing: f64) -> bool {
+        let mut world = new_world(1);
+        world.update(TICK_DT); // settle the player inside the arena
+        let at = world.player_pos() + Position::new(150.0, 0.0);
+        let enemy = Enemy::spawn(&mut world.entities, at, EnemyType::Shielded, 1.0, &world.config.enemy, &mut world.rng);
+        world.entities.transforms.get_mut(enemy).unwrap().angle = facing;
+        let health = world.entities.healths.get(enemy).unwrap().value;
+
+        world.step(TICK_DT, &[GameInput::Aim(at.x, at.y), GameInput::StartFire, GameInput::StopFire]);
+        for _ in 0..(0.5 * TICK_RATE) as usize {
+            world.update(TICK_DT);
+        }
+        world.entities.healths.get(enemy).is_none_or(|left| left.value < health)
+    }
+
+    #[test]
+    fn shields_turn_slow_enough_to_be_shot_from_behind() {
+        assert!(!shot_hurts_shielded(PI));
+        assert!(shot_hurts_shielded(0.0));
+    }
+
+    #[test]
+    fn walking_over_a_pickup_starts_its_buff() {
+        let mut world = new_world(1);
+        world.config.pickups.chance = 1.0;
+        world.config.pickups.mix = [(PickupKind::Shield, 1.0)].into_iter().collect();
+
+        let at = world.player_pos() + Position::new(100.0, 0.0);
+        let enemy = Enemy::spawn(&mut world.entities, at, EnemyType::Kind, 1.0, &world.config.enemy, &mut world.rng);
+        world.drop_pickup(enemy);
+        assert_eq!(world.entities.pickups.len(), 1);
+
+        world.teleport_player(at);
+        world.entities.despawn(enemy);
+        world.update(TICK_DT);
+        assert!(world.entities.pickups.is_empty());
+        assert!(world.player().buffs.contains_key(&PickupKind::Shield));
+        assert!(!world.entities.can_be_hurt(world.player));
+        // only a respawn blinks
+        assert!(!world.entities.invulnerables.contains(world.player));
+
+        // and it wears off again
+        for _ in 0..(world.config.pickups.duration * TICK_RATE).ceil() as usize + 1 {
+            world.update(TICK_DT);
+        }
+        assert!(world.player().buffs.is_empty());
+        assert!(world.entities.can_be_hurt(world.player));
+    }
+
+    fn world_with_bruiser_on_player() -> (GameWorld, Entity) {
+        let mut world = new_world(1);
+        world.update(TICK_DT); // settle the player inside the arena
+        let at = world.player_pos();
+        let bruiser = Enemy::spawn(&mut world.entities, at, EnemyType::Bruiser, 1.0, &world.config.enemy, &mut world.rng);
+        (world, bruiser)
+    }
+
+    #[test]
+    fn touching_a_monster_hurts_instead_of_taking_a_life() {
+        let (mut world, _) = world_with_bruiser_on_player();
+        let (life, health) = (world.player().life, world.player_health());
+        for _ in 0..10 {
+            world.update(TICK_DT);
+        }
+
+        assert_eq!(world.player().life, life);
+        let lost = health - world.player_health();
+        assert!(lost > 0.0 && lost <= world.config.enemy.bruiser.contact * 10.0 * TICK_DT + 1e-9);
+    }
+
+    #[test]
+    fn dying_respawns_away_from_monsters_for_a_while_unhurt() {
+        let (mut world, bruiser) = world_with_bruiser_on_player();
+        let monster = world.entities.transforms.get(bruiser).unwrap().pos;
+        let life = world.player().life;
+        world.entities.healths.get_mut(world.player).unwrap().value = 0.0;
+        world.update(TICK_DT);
+
+        assert!(matches!(world.events[..], [GameEvent::Died { .. }, GameEvent::Respawned { .. }]));
+        assert_eq!(world.player().life, life - 1);
+        assert_eq!(world.player_health(), world.config.player.health);
+        assert!(world.player_pos().distance(&monster) > 100.0);
+        assert!(world.entities.invulnerables.contains(world.player));
+
+        // the last life ends the run
+        world.player_mut().life = 1;
+        world.entities.invulnerables.remove(world.player);
+        world.entities.healths.get_mut(world.player).unwrap().value = 0.0;
+        world.update(TICK_DT);
+        assert_eq!(world.events, vec![GameEvent::Died { at: world.player_pos(), lives: 0 }]);
+        assert_eq!(world.status, GameStatus::Lose);
+    }
+
+    #[test]
+    fn the_last_round_starts_a_reload() {
+        let mut world = new_world(1);
+        world.player_mut().weapon_mut().rounds = 1;
+        world.step(TICK_DT, &[GameInput::StartFire, GameInput::StopFire]);
+
+        let weapon = world.player().weapon();
+        assert_eq!(world.entities.bullets.len(), 1);
+        assert!(weapon.is_empty());
+        assert!(weapon.reloading.is_some());
+
+        let secs = weapon.config.reload_time;
+        for _ in 0..(secs * TICK_RATE).ceil() as usize + 1 {
+            world.update(TICK_DT);
+        }
+        assert_eq!(world.player().weapon().rounds, world.player().weapon().config.magazine);
+    }
+
+    #[test]
+    fn every_weapon_has_its_own_fire_rate() {
+        let mut world = new_world(1);
+        let weapons = WeaponConfig::defaults();
+        let (slow, fast) = (weapons.len() - 1, 0);
+        assert!(weapons[slow].fire_cooldown > weapons[fast].fire_cooldown * 2.0);
+
+        world.step(TICK_DT, &[GameInput::SelectWeapon(slow), GameInput::StartFire, GameInput::StopFire]);
+        assert_eq!(world.entities.bullets.len(), 1);
+        // right away with another weapon, while the first one still cools down
+        world.step(TICK_DT, &[GameInput::SelectWeapon(fast), GameInput::StartFire, GameInput::StopFire]);
+        assert_eq!(world.entities.bullets.len(), 2);
+        assert!(!world.player().weapons[slow].is_ready());
+    }
+
+    #[test]
+    fn holding_fire_only_repeats_automatic_weapons() {
+        let weapons = WeaponConfig::defaults();
+        let pistol = weapons.iter().position(|weapon| !weapon.automatic).unwrap();
+        let smg = weapons.iter().position(|weapon| weapon.automatic).unwrap();
+
+        assert_eq!(shots_while_held(pistol, 1.0), 1);
+        // at its fire rate, starting with a shot right away
+        let expected = (0.5 / weapons[smg].fire_cooldown).floor() as u32 + 1;
+        assert_eq!(shots_while_held(smg, 0.5), expected);
+    }
+}